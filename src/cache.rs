@@ -0,0 +1,561 @@
+use crate::config::{replace_home, AppConfig};
+use crate::feed::{Flag, Item, ItemKind};
+use crate::fetch::ChannelStatus;
+use crate::id::entry_id;
+use crate::query::Query;
+use crate::resolve::Channel;
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+
+pub type ChannelEtags = HashMap<String, Option<String>>;
+pub type ChannelStatuses = HashMap<String, ChannelStatus>;
+
+/// Which items the list shows, on top of the filter
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum ViewMode {
+    #[default]
+    All,
+    /// items neither watched nor skipped yet
+    Unread,
+    Starred,
+    InProgress,
+    Queued,
+    /// items in exactly this state
+    State(Flag),
+}
+
+impl ViewMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ViewMode::All => "all",
+            ViewMode::Unread => "unread",
+            ViewMode::Starred => "starred",
+            ViewMode::InProgress => "in progress",
+            ViewMode::Queued => "queued",
+            ViewMode::State(flag) => flag.name(),
+        }
+    }
+
+    /// Next mode in the cycle all, unread, starred, in progress, queued
+    pub fn next(&self) -> ViewMode {
+        match self {
+            ViewMode::All => ViewMode::Unread,
+            ViewMode::Unread => ViewMode::Starred,
+            ViewMode::Starred => ViewMode::InProgress,
+            ViewMode::InProgress => ViewMode::Queued,
+            ViewMode::Queued | ViewMode::State(_) => ViewMode::All,
+        }
+    }
+
+    pub fn matches(&self, videos: &Items, video: &Item) -> bool {
+        match self {
+            ViewMode::All => true,
+            ViewMode::Unread => !matches!(video.flag, Flag::Watched | Flag::Skipped),
+            ViewMode::Starred => video.flag == Flag::Starred,
+            ViewMode::InProgress => matches!(video.flag, Flag::InProgress { .. }),
+            ViewMode::Queued => is_queued(videos, video),
+            ViewMode::State(flag) => flag.same_state(&video.flag),
+        }
+    }
+}
+
+/// Order of the list, reversed page by page when `sort` is `desc`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortKey {
+    /// newest first
+    #[default]
+    Published,
+    /// most recently fetched first
+    FirstSeen,
+    /// by channel name, newest first within a channel
+    Channel,
+    Title,
+    /// shortest first, unknown durations last
+    Duration,
+    /// items neither watched nor skipped first, newest first
+    UnreadFirst,
+    /// newest item of each channel, then the second newest of each... so
+    /// that a prolific channel does not fill the page
+    RoundRobin,
+}
+
+impl SortKey {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortKey::Published => "published",
+            SortKey::FirstSeen => "first seen",
+            SortKey::Channel => "channel",
+            SortKey::Title => "title",
+            SortKey::Duration => "duration",
+            SortKey::UnreadFirst => "unread first",
+            SortKey::RoundRobin => "round robin",
+        }
+    }
+
+    pub fn next(&self) -> SortKey {
+        match self {
+            SortKey::Published => SortKey::FirstSeen,
+            SortKey::FirstSeen => SortKey::Channel,
+            SortKey::Channel => SortKey::Title,
+            SortKey::Title => SortKey::Duration,
+            SortKey::Duration => SortKey::UnreadFirst,
+            SortKey::UnreadFirst => SortKey::RoundRobin,
+            SortKey::RoundRobin => SortKey::Published,
+        }
+    }
+
+    /// Sorts items already sorted newest first
+    pub fn sort(&self, videos: &mut Vec<Item>) {
+        match self {
+            SortKey::Published => (),
+            SortKey::FirstSeen => videos.sort_by(|a, b| b.first_seen.cmp(&a.first_seen)),
+            SortKey::Channel => {
+                videos.sort_by_key(|video| video.channel.to_lowercase());
+            }
+            SortKey::Title => videos.sort_by_key(|video| video.title.to_lowercase()),
+            SortKey::Duration => videos.sort_by_key(|video| video.duration.unwrap_or(u64::MAX)),
+            SortKey::UnreadFirst => {
+                videos.sort_by_key(|video| matches!(video.flag, Flag::Watched | Flag::Skipped))
+            }
+            SortKey::RoundRobin => {
+                let mut channels: Vec<(String, std::collections::VecDeque<Item>)> = vec![];
+                for video in videos.drain(..) {
+                    match channels
+                        .iter_mut()
+                        .find(|(url, _)| *url == video.channel_url)
+                    {
+                        Some((_, items)) => items.push_back(video),
+                        None => channels.push((video.channel_url.clone(), vec![video].into())),
+                    }
+                }
+                while !channels.is_empty() {
+                    for (_, items) in channels.iter_mut() {
+                        videos.extend(items.pop_front());
+                    }
+                    channels.retain(|(_, items)| !items.is_empty());
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Items {
+    pub channel_etags: ChannelEtags,
+    #[serde(default)]
+    pub channel_statuses: ChannelStatuses,
+    /// date of the last full refresh, items first seen then are new
+    #[serde(default)]
+    pub last_refresh: Option<String>,
+    /// ids of the items to play next, in order
+    #[serde(default)]
+    pub queue: Vec<String>,
+    /// view mode of the terminal UI, kept across restarts
+    #[serde(default)]
+    pub view_mode: ViewMode,
+    /// whether YouTube items are shorts, by item id, see `fetch::classify_shorts`
+    #[serde(default)]
+    pub shorts: HashMap<String, bool>,
+    pub videos: Vec<Item>,
+}
+
+pub fn load_cache(app_config: &AppConfig) -> io::Result<Items> {
+    let path = replace_home(&app_config.cache_path);
+    let s = fs::read_to_string(path)?;
+    let mut videos: Items = serde_json::from_str(s.as_str())?;
+    // caches written before ids existed
+    for video in videos.videos.iter_mut().filter(|video| video.id.is_empty()) {
+        video.id = entry_id(
+            &video.url,
+            None,
+            &video.channel_url,
+            &video.title,
+            &video.published,
+        );
+    }
+    Ok(videos)
+}
+
+/// Writes the cache, and the search index when items were added or removed
+pub fn save_videos(app_config: &AppConfig, videos: &Items) -> io::Result<()> {
+    let proper_path = replace_home(&app_config.cache_path);
+    let serialized = serde_json::to_string(&videos)?;
+    fs::write(proper_path, serialized)
+}
+
+/// Lookup of items by canonical id, falling back on the url for items
+/// whose id changed because they were cached before ids existed
+pub struct ItemIndex<'a> {
+    by_id: HashMap<&'a str, &'a Item>,
+    by_url: HashMap<&'a str, &'a Item>,
+}
+
+impl<'a> ItemIndex<'a> {
+    pub fn new<I: IntoIterator<Item = &'a Item>>(videos: I) -> ItemIndex<'a> {
+        let mut index = ItemIndex {
+            by_id: HashMap::new(),
+            by_url: HashMap::new(),
+        };
+        for video in videos {
+            index.by_id.entry(video.id.as_str()).or_insert(video);
+            index.by_url.entry(video.url.as_str()).or_insert(video);
+        }
+        index
+    }
+
+    pub fn get(&self, video: &Item) -> Option<&'a Item> {
+        self.by_id
+            .get(video.id.as_str())
+            .or_else(|| self.by_url.get(video.url.as_str()))
+            .copied()
+    }
+}
+
+/// Lowercased alphanumeric words of a title, so that mirrors adding
+/// punctuation or changing case still match
+fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Secondary identity used to spot copies which do not share an id, only
+/// available when the feed tells the duration
+fn title_duration_key(video: &Item) -> Option<String> {
+    video
+        .duration
+        .map(|duration| format!("{}/{}", normalize_title(&video.title), duration))
+}
+
+/// Collapses copies of the same item fetched from several channels (same
+/// id, or same normalized title and duration) into the first one, which
+/// then lists the other channels in `sources` and their ids in `aliases`.
+/// The cache keeps every copy, collapsing only happens for display.
+pub fn collapse_duplicates(videos: &mut Vec<Item>) {
+    let mut kept: Vec<Item> = vec![];
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for video in videos.drain(..) {
+        let keys = [Some(video.id.clone()), title_duration_key(&video)]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<String>>();
+        match keys.iter().find_map(|key| positions.get(key)).copied() {
+            Some(i) => {
+                let first = &mut kept[i];
+                if video.channel != first.channel && !first.sources.contains(&video.channel) {
+                    first.sources.push(video.channel);
+                }
+                if video.id != first.id && !first.aliases.contains(&video.id) {
+                    first.aliases.push(video.id);
+                }
+                for key in keys {
+                    positions.entry(key).or_insert(i);
+                }
+            }
+            None => {
+                for key in keys {
+                    positions.insert(key, kept.len());
+                }
+                kept.push(video);
+            }
+        }
+    }
+    *videos = kept;
+}
+
+/// Carries the state kept for already known items (flag, first seen date,
+/// rule results) over to freshly fetched ones and stamps new items with the
+/// date of this refresh
+pub fn merge_state(videos: &mut Items, original_videos: &Items, now: &str) {
+    let index = ItemIndex::new(&original_videos.videos);
+    for vid in videos.videos.iter_mut() {
+        match index.get(vid) {
+            Some(original) => {
+                vid.flag = original.flag.clone();
+                vid.hidden = original.hidden;
+                // rules only apply to items fetched after they are added
+                vid.rules = original.rules.clone();
+                vid.tags = original.tags.clone();
+                // caches written before first_seen existed only know the publication date
+                vid.first_seen = Some(
+                    original
+                        .first_seen
+                        .clone()
+                        .unwrap_or(original.published.clone()),
+                );
+            }
+            None => vid.first_seen = Some(now.to_string()),
+        }
+    }
+    videos.last_refresh = Some(now.to_string());
+    videos.view_mode = original_videos.view_mode.clone();
+    let ids = videos
+        .videos
+        .iter()
+        .map(|video| video.id.as_str())
+        .collect::<HashSet<&str>>();
+    videos.shorts = original_videos
+        .shorts
+        .iter()
+        .filter(|(id, _)| ids.contains(id.as_str()))
+        .map(|(id, short)| (id.clone(), *short))
+        .collect();
+    // items which left their feed cannot be played from the queue anymore
+    videos.queue = original_videos
+        .queue
+        .iter()
+        .filter(|id| ids.contains(id.as_str()))
+        .cloned()
+        .collect();
+}
+
+/// Forgets the items and fetch status of a channel unsubscribed from
+pub fn remove_channel(videos: &mut Items, channel: &Channel) {
+    videos
+        .videos
+        .retain(|video| !channel.has_feed(&video.channel_url));
+    videos.channel_etags.retain(|url, _| !channel.has_feed(url));
+    videos
+        .channel_statuses
+        .retain(|url, _| !channel.has_feed(url));
+    let ids = videos
+        .videos
+        .iter()
+        .map(|video| video.id.as_str())
+        .collect::<HashSet<&str>>();
+    videos.queue.retain(|id| ids.contains(id.as_str()));
+}
+
+/// Sets the kind of the items known to be shorts
+pub fn set_short_kinds(videos: &mut Items) {
+    for video in videos.videos.iter_mut() {
+        if videos.shorts.get(&video.id) == Some(&true) {
+            video.kind = ItemKind::Short;
+        }
+    }
+}
+
+/// Name of a channel as seen in its items, falling back to its url
+pub fn channel_name<'a>(videos: &'a Items, channel_url: &'a str) -> &'a str {
+    videos
+        .videos
+        .iter()
+        .find(|video| video.channel_url == channel_url)
+        .map_or(channel_url, |video| video.channel.as_str())
+}
+
+pub fn failing_channels(videos: &Items) -> usize {
+    videos
+        .channel_statuses
+        .values()
+        .filter(|status| !status.is_ok())
+        .count()
+}
+
+/// Channel statuses with failing channels first, then sorted by url
+pub fn sorted_channel_statuses(videos: &Items) -> Vec<(&String, &ChannelStatus)> {
+    let mut statuses = videos.channel_statuses.iter().collect::<Vec<_>>();
+    statuses.sort_by(|(url_a, a), (url_b, b)| a.is_ok().cmp(&b.is_ok()).then(url_a.cmp(url_b)));
+    statuses
+}
+
+/// Sets the flag of an item and of all the copies collapsed into it
+pub fn set_flag(videos: &mut [Item], item: &Item, flag: &Flag) {
+    for vid in videos.iter_mut() {
+        if vid.id == item.id || item.aliases.contains(&vid.id) {
+            vid.flag = flag.clone();
+        }
+    }
+}
+
+/// Hides or shows back an item and all the copies collapsed into it
+pub fn set_hidden(videos: &mut [Item], item: &Item, hidden: bool) {
+    for vid in videos.iter_mut() {
+        if vid.id == item.id || item.aliases.contains(&vid.id) {
+            vid.hidden = hidden;
+        }
+    }
+}
+
+/// Whether an item was published before the given day, in its own time zone
+pub fn published_before(video: &Item, date: &NaiveDate) -> bool {
+    video
+        .published
+        .get(..10)
+        .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+        .is_some_and(|day| day < *date)
+}
+
+/// Tags the items (copies collapsed) matching `matches` as watched, leaving
+/// starred and skipped ones alone. Returns the number of items which changed.
+pub fn mark_read<F: Fn(&Item) -> bool>(videos: &mut [Item], matches: F) -> usize {
+    let mut rows = videos.to_vec();
+    collapse_duplicates(&mut rows);
+    let rows = rows
+        .into_iter()
+        .filter(|row| {
+            !matches!(row.flag, Flag::Starred | Flag::Watched | Flag::Skipped) && matches(row)
+        })
+        .collect::<Vec<Item>>();
+    for row in rows.iter() {
+        set_flag(videos, row, &Flag::Watched);
+    }
+    rows.len()
+}
+
+/// Ids of the items fetched from a channel, including copies which
+/// `collapse_duplicates` folds into items of other channels
+pub fn channel_item_ids(videos: &[Item], channel_url: &str) -> HashSet<String> {
+    videos
+        .iter()
+        .filter(|video| video.channel_url == channel_url)
+        .map(|video| video.id.clone())
+        .collect()
+}
+
+/// Whether an item or one of the copies collapsed into it has one of `ids`
+pub fn has_id(item: &Item, ids: &HashSet<String>) -> bool {
+    ids.contains(&item.id) || item.aliases.iter().any(|alias| ids.contains(alias))
+}
+
+pub fn is_queued(videos: &Items, item: &Item) -> bool {
+    videos
+        .queue
+        .iter()
+        .any(|id| *id == item.id || item.aliases.contains(id))
+}
+
+/// Adds an item at the end of the queue, or removes it if it is already
+/// queued. Returns whether the item is now queued.
+pub fn toggle_queued(videos: &mut Items, item: &Item) -> bool {
+    if is_queued(videos, item) {
+        videos
+            .queue
+            .retain(|id| *id != item.id && !item.aliases.contains(id));
+        false
+    } else {
+        videos.queue.push(item.id.clone());
+        true
+    }
+}
+
+/// Queued items in queue order, copies collapsed as in the list
+pub fn queued_items(videos: &Items) -> Vec<Item> {
+    let mut collapsed = videos.videos.clone();
+    collapse_duplicates(&mut collapsed);
+    videos
+        .queue
+        .iter()
+        .filter_map(|id| {
+            collapsed
+                .iter()
+                .find(|video| video.id == *id || video.aliases.contains(id))
+                .cloned()
+        })
+        .collect()
+}
+
+/// Moves the item at position `i` of the queue up (negative offset) or down
+pub fn move_queued(videos: &mut Items, i: usize, offset: isize) -> usize {
+    let Some(j) = i
+        .checked_add_signed(offset)
+        .filter(|j| *j < videos.queue.len())
+    else {
+        return i;
+    };
+    videos.queue.swap(i, j);
+    j
+}
+
+pub fn is_new(videos: &Items, video: &Item) -> bool {
+    videos.last_refresh.is_some() && video.first_seen == videos.last_refresh
+}
+
+/// Number of new items per channel, most active channels first, muted
+/// channels aside
+pub fn new_items_by_channel(app_config: &AppConfig, videos: &Items) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for video in videos.videos.iter().filter(|video| {
+        is_new(videos, video)
+            && !app_config
+                .channel_settings(&video.channel_url)
+                .is_some_and(|settings| settings.muted)
+    }) {
+        *counts.entry(video.channel.as_str()).or_default() += 1;
+    }
+    let mut counts = counts
+        .into_iter()
+        .map(|(channel, count)| (channel.to_string(), count))
+        .collect::<Vec<(String, usize)>>();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    counts
+}
+
+/// One line summary of new items, such as `3 new: Linus Tech Tips 2, Late Night Linux 1`
+pub fn new_items_summary(app_config: &AppConfig, videos: &Items) -> String {
+    let counts = new_items_by_channel(app_config, videos);
+    if counts.is_empty() {
+        return "no new item".to_string();
+    }
+    let total = counts.iter().map(|(_, count)| count).sum::<usize>();
+    let channels = counts
+        .iter()
+        .map(|(channel, count)| format!("{} {}", channel, count))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("{} new: {}", total, channels)
+}
+
+/// Page of items to show, copies collapsed, channel settings applied,
+/// matching the view mode and the filter, sorted by channel priority then
+/// `sort_key`
+pub fn to_show_videos(
+    app_config: &AppConfig,
+    videos: &mut Items,
+    start: usize,
+    end: usize,
+    filter: &Query,
+) -> Vec<Item> {
+    videos.videos.sort_by(|a, b| b.published.cmp(&a.published));
+    let mut collapsed = videos.videos.to_vec();
+    collapse_duplicates(&mut collapsed);
+    let mut filtered_videos = collapsed
+        .into_iter()
+        .filter(|video| !video.hidden)
+        .filter(|video| !(app_config.hide_shorts && matches!(video.kind, ItemKind::Short)))
+        .filter_map(
+            |video| match app_config.channel_settings(&video.channel_url) {
+                Some(settings) => settings.apply(video),
+                None => Some(video),
+            },
+        )
+        .filter(|video| videos.view_mode.matches(videos, video))
+        .filter(|video| filter.matches(video))
+        .collect::<Vec<Item>>();
+    app_config.sort_key.sort(&mut filtered_videos);
+    filtered_videos.sort_by_key(|video| {
+        std::cmp::Reverse(
+            app_config
+                .channel_settings(&video.channel_url)
+                .map_or(0, |settings| settings.priority),
+        )
+    });
+    let new_end = std::cmp::min(end, filtered_videos.len());
+    let mut result = filtered_videos[start.min(new_end)..new_end].to_vec();
+    if app_config.sort == "desc" {
+        result.reverse()
+    }
+    result
+}
+
+pub fn filter_regex(s: &str) -> Result<Regex, regex::Error> {
+    let wildcard_s = s.replace("", ".*");
+    Regex::new(&format!(".*(?i){}.*", wildcard_s))
+}
@@ -0,0 +1,405 @@
+use crate::cache::{SortKey, ViewMode};
+use crate::feed::{Item, ItemKind};
+use crate::resolve::Channel;
+use crate::rules::Rule;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind::NotFound};
+use std::path::Path;
+
+pub fn youtube_base_url() -> String {
+    "https://www.youtube.com/".to_string()
+}
+
+pub fn default_kind_symbols() -> HashMap<String, String> {
+    let mut symbols: HashMap<String, String> = HashMap::new();
+    symbols.insert("Audio".to_string(), "a".to_string());
+    symbols.insert("Video".to_string(), "v".to_string());
+    symbols.insert("Short".to_string(), "s".to_string());
+    symbols.insert("Other".to_string(), "o".to_string());
+    symbols.insert("Magnet".to_string(), "m".to_string());
+    symbols
+}
+
+pub fn default_flag_symbols() -> HashMap<String, String> {
+    let mut symbols: HashMap<String, String> = HashMap::new();
+    symbols.insert("Unread".to_string(), " ".to_string());
+    symbols.insert("InProgress".to_string(), "◐".to_string());
+    symbols.insert("Watched".to_string(), "✓".to_string());
+    symbols.insert("Skipped".to_string(), "✗".to_string());
+    symbols.insert("Starred".to_string(), "★".to_string());
+    symbols.insert("WatchLater".to_string(), "◷".to_string());
+    symbols
+}
+
+/// Saved filter, picked in the terminal UI with tab or '
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NamedView {
+    pub name: String,
+    /// filter query, see `query::Query`
+    #[serde(default)]
+    pub query: String,
+    /// overrides `sort` while the view is active
+    #[serde(default)]
+    pub sort: Option<String>,
+    /// overrides `sort_key` while the view is active
+    #[serde(default)]
+    pub sort_key: Option<SortKey>,
+    #[serde(default)]
+    pub view_mode: ViewMode,
+}
+
+/// Settings of one channel, in `channels` under its feed url (or channel id
+/// for YouTube channels)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ChannelSettings {
+    /// shown instead of the feed title
+    pub name: Option<String>,
+    /// new items are left out of the refresh summary and notification
+    pub muted: bool,
+    /// items are left out of the list
+    pub hidden: bool,
+    /// items of channels with a higher priority come first, 0 by default
+    pub priority: i64,
+    /// kind of all the items, `Audio` ones being played without video by mpv
+    pub kind: Option<ItemKind>,
+    /// overrides `youtubedl_format`
+    pub youtubedl_format: Option<String>,
+    /// added to `player_additional_opts`
+    pub player_additional_opts: Vec<String>,
+    /// items whose title contains one of these (ignoring case) are left out
+    pub blocked_keywords: Vec<String>,
+}
+
+impl ChannelSettings {
+    /// Item as listed, `None` when the channel is hidden or the title blocked
+    pub fn apply(&self, mut video: Item) -> Option<Item> {
+        let title = video.title.to_lowercase();
+        if self.hidden
+            || self
+                .blocked_keywords
+                .iter()
+                .any(|keyword| title.contains(&keyword.to_lowercase()))
+        {
+            return None;
+        }
+        if let Some(name) = &self.name {
+            video.channel = name.clone();
+        }
+        if let Some(kind) = &self.kind {
+            video.kind = kind.clone();
+        }
+        Some(video)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AppConfig {
+    pub video_path: String,
+    pub cache_path: String,
+    pub youtubedl_format: String,
+    pub player_additional_opts: Vec<String>,
+    pub video_extension: String,
+    pub kind_symbols: HashMap<String, String>,
+    pub flag_symbols: HashMap<String, String>,
+    pub blockish_player: Option<String>,
+    pub players: Vec<Vec<String>>,
+    pub channel_ids: Vec<String>,
+    pub channel_urls: Vec<String>,
+    pub mpv_mode: bool,
+    pub mpv_path: String,
+    pub fs: bool,
+    pub open_magnet: Option<String>,
+    pub sort: String,
+    pub sort_key: SortKey,
+    pub auto_thumbnail_path: Option<String>,
+    pub split_thumbnail: bool,
+    pub youtube_instance: String,
+    pub short_check_backward_days: usize,
+    pub hide_shorts: bool,
+    pub watched_percent: u64,
+    pub views: Vec<NamedView>,
+    pub channels: HashMap<String, ChannelSettings>,
+    pub rules: Vec<Rule>,
+}
+
+impl Default for AppConfig {
+    fn default() -> AppConfig {
+        AppConfig {
+            kind_symbols: default_kind_symbols(),
+            flag_symbols: default_flag_symbols(),
+            video_path: "/tmp".to_string(),
+            cache_path: "__HOME/.cache/yts/yts.json".to_string(),
+            youtubedl_format: "[height <=? 360][ext = mp4]".to_string(),
+            player_additional_opts: vec![],
+            video_extension: "mp4".to_string(),
+            blockish_player: None,
+            players: vec![
+                vec![
+                    "/usr/bin/omxplayer".to_string(),
+                    "-o".to_string(),
+                    "local".to_string(),
+                ],
+                vec![
+                    "/Applications/VLC.app/Contents/MacOS/VLC".to_string(),
+                    "--play-and-exit".to_string(),
+                    "-f".to_string(),
+                ],
+                vec![
+                    "/usr/bin/vlc".to_string(),
+                    "--play-and-exit".to_string(),
+                    "-f".to_string(),
+                ],
+                vec![
+                    "/usr/bin/mpv".to_string(),
+                    "-really-quiet".to_string(),
+                    "-fs".to_string(),
+                ],
+                vec![
+                    "/usr/bin/mplayer".to_string(),
+                    "-really-quiet".to_string(),
+                    "-fs".to_string(),
+                ],
+            ],
+            channel_ids: vec![],
+            channel_urls: vec![],
+            mpv_mode: true,
+            mpv_path: "/usr/bin/mpv".to_string(),
+            fs: true,
+            open_magnet: None,
+            sort: "desc".to_string(),
+            sort_key: SortKey::Published,
+            auto_thumbnail_path: None,
+            split_thumbnail: false,
+            youtube_instance: youtube_base_url(),
+            short_check_backward_days: 1,
+            hide_shorts: false,
+            watched_percent: 90,
+            views: vec![],
+            channels: HashMap::new(),
+            rules: vec![],
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn channel_settings(&self, channel_url: &str) -> Option<&ChannelSettings> {
+        self.channels.get(channel_url).or_else(|| {
+            channel_url
+                .split_once("channel_id=")
+                .and_then(|(_, id)| self.channels.get(id))
+        })
+    }
+
+    /// Adds a channel to `channel_ids` or `channel_urls`, false if it is
+    /// already there
+    pub fn add_channel(&mut self, channel: &Channel) -> bool {
+        let (list, value) = match channel {
+            Channel::Id(id) => (&mut self.channel_ids, id),
+            Channel::Url(url) => (&mut self.channel_urls, url),
+        };
+        if list.contains(value) {
+            return false;
+        }
+        list.push(value.clone());
+        true
+    }
+
+    /// Removes a channel from `channel_ids` and `channel_urls`, false if it
+    /// was in none of them
+    pub fn remove_channel(&mut self, channel: &Channel) -> bool {
+        let count = self.channel_ids.len() + self.channel_urls.len();
+        self.channel_ids
+            .retain(|id| Channel::Id(id.clone()) != *channel);
+        self.channel_urls.retain(|url| !channel.has_feed(url));
+        count != self.channel_ids.len() + self.channel_urls.len()
+    }
+
+    /// Configuration to play or download items of a channel with
+    pub fn for_channel(&self, channel_url: &str) -> AppConfig {
+        let mut app_config = self.clone();
+        if let Some(settings) = self.channel_settings(channel_url) {
+            if let Some(format) = &settings.youtubedl_format {
+                app_config.youtubedl_format = format.clone();
+            }
+            app_config
+                .player_additional_opts
+                .extend(settings.player_additional_opts.iter().cloned());
+        }
+        app_config
+    }
+}
+
+pub fn default_config_path() -> Option<String> {
+    dirs::home_dir().and_then(|home| {
+        home.to_str()
+            .map(|h| format!("{}/.config/youtube-subscriptions/config.json", h))
+    })
+}
+
+pub fn read_config(config_path: &str) -> Result<AppConfig, std::io::Error> {
+    let s = fs::read_to_string(config_path)?;
+    Ok(serde_json::from_str::<AppConfig>(s.as_str())?)
+}
+
+pub fn save_config(config_path: &str, app_config: &AppConfig) -> Result<(), std::io::Error> {
+    if let Some(parent) = Path::new(config_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let config_as_string = serde_json::to_string_pretty(app_config)?;
+    fs::write(config_path, config_as_string)
+}
+
+pub fn load_config(
+    config_path: Option<&str>,
+    cache_path: Option<&str>,
+) -> Result<AppConfig, std::io::Error> {
+    let mut _res = match config_path {
+        Some(path) => match read_config(path) {
+            Err(e) if e.kind() == NotFound => {
+                let default_config = AppConfig {
+                    ..Default::default()
+                };
+                save_config(path, &default_config)?;
+                default_config
+            }
+            res => res?,
+        },
+        None => AppConfig {
+            ..Default::default()
+        },
+    };
+    if let Some(path) = cache_path {
+        _res.cache_path = path.to_string();
+    }
+
+    _res.video_path = replace_home(&_res.video_path);
+    fs::create_dir_all(&_res.video_path)?;
+
+    if let Some(parent) = Path::new(&replace_home(&_res.cache_path)).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(_res)
+}
+
+/// Adds or removes a channel in the configuration file, only rewriting
+/// `channel_ids` and `channel_urls` so that runtime changes (such as the sort
+/// switched in the terminal UI), defaults and unknown keys are left as they
+/// are. False when there was nothing to change.
+pub fn edit_channel(config_path: &str, channel: &Channel, add: bool) -> io::Result<bool> {
+    let mut json = match fs::read_to_string(config_path) {
+        Err(e) if e.kind() == NotFound => serde_json::Value::Object(Default::default()),
+        res => serde_json::from_str(&res?)?,
+    };
+    let object = json.as_object_mut().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "configuration is not an object")
+    })?;
+    let strings = |key: &str| -> Vec<String> {
+        object
+            .get(key)
+            .and_then(|list| list.as_array())
+            .map(|list| {
+                list.iter()
+                    .filter_map(|value| value.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let mut lists = AppConfig {
+        channel_ids: strings("channel_ids"),
+        channel_urls: strings("channel_urls"),
+        ..Default::default()
+    };
+    let (ids, urls) = (lists.channel_ids.len(), lists.channel_urls.len());
+    let changed = if add {
+        lists.add_channel(channel)
+    } else {
+        lists.remove_channel(channel)
+    };
+    if !changed {
+        return Ok(false);
+    }
+    if ids != lists.channel_ids.len() {
+        object.insert("channel_ids".to_string(), lists.channel_ids.into());
+    }
+    if urls != lists.channel_urls.len() {
+        object.insert("channel_urls".to_string(), lists.channel_urls.into());
+    }
+    if let Some(parent) = Path::new(config_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(config_path, serde_json::to_string_pretty(&json)?)?;
+    Ok(true)
+}
+
+/// OPML document without the outlines of a channel
+pub fn remove_outlines(xml: &str, channel: &Channel) -> String {
+    let outline = Regex::new(r#"<outline\b[^>]*\bxmlUrl="([^"]*)"[^>]*?(?:/>|>\s*</outline>)\s*"#)
+        .expect("outline regex");
+    outline
+        .replace_all(xml, |caps: &regex::Captures| {
+            if channel.has_feed(&caps[1].replace("&amp;", "&")) {
+                "".to_string()
+            } else {
+                caps[0].to_string()
+            }
+        })
+        .to_string()
+}
+
+/// Removes the outlines of a channel from the subscription_manager OPML
+/// file, false if it had none
+pub fn remove_from_subscription_manager(channel: &Channel) -> io::Result<bool> {
+    let home = dirs::home_dir().expect("home dir");
+    let path = home.join(subscription_manager_relative_path());
+    let xml = match fs::read_to_string(&path) {
+        Err(e) if e.kind() == NotFound => return Ok(false),
+        res => res?,
+    };
+    let removed = remove_outlines(&xml, channel);
+    if removed == xml {
+        return Ok(false);
+    }
+    fs::write(&path, removed)?;
+    Ok(true)
+}
+
+/// Removes a channel from the configuration and from the subscription
+/// manager file, which would bring it back otherwise. Returns whether it
+/// was in one of them.
+pub fn unsubscribe(config_path: &str, channel: &Channel) -> Result<bool, String> {
+    let in_config = edit_channel(config_path, channel, false)
+        .map_err(|e| format!("failed writing {}: {}", config_path, e))?;
+    let in_opml = remove_from_subscription_manager(channel).map_err(|e| {
+        format!(
+            "failed writing ~/{}: {}",
+            subscription_manager_relative_path(),
+            e
+        )
+    })?;
+    Ok(in_config || in_opml)
+}
+
+pub fn subscriptions_url() -> &'static str {
+    "https://www.youtube.com/subscription_manager?action_takeout=1"
+}
+
+pub fn subscription_manager_relative_path() -> &'static str {
+    ".config/youtube-subscriptions/subscription_manager"
+}
+
+pub fn replace_home(path: &str) -> String {
+    let home = dirs::home_dir().expect("home dir");
+    path.replace("__HOME", home.to_str().expect("home as str"))
+}
+
+pub fn is_channel_id(s: &str) -> bool {
+    match Regex::new(r"^UC[0-9A-Za-z_-]{22}$") {
+        Ok(re) => re.is_match(s),
+        Err(_) => false,
+    }
+}
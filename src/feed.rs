@@ -0,0 +1,586 @@
+use crate::id::entry_id;
+use chrono::DateTime;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Watch state of an item
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum Flag {
+    #[default]
+    Unread,
+    /// partially played, `position` being the resume point in seconds
+    InProgress {
+        position: u64,
+    },
+    /// called `Read` before the other states existed
+    #[serde(alias = "Read")]
+    Watched,
+    Skipped,
+    Starred,
+    WatchLater,
+}
+
+impl Flag {
+    /// Name used for symbols in the configuration and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Flag::Unread => "Unread",
+            Flag::InProgress { .. } => "InProgress",
+            Flag::Watched => "Watched",
+            Flag::Skipped => "Skipped",
+            Flag::Starred => "Starred",
+            Flag::WatchLater => "WatchLater",
+        }
+    }
+
+    /// Whether both flags are the same state, whatever the resume position
+    pub fn same_state(&self, other: &Flag) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// Caches store unread items with a `null` flag
+fn deserialize_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Flag, D::Error> {
+    Ok(Option::<Flag>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+fn default_content() -> Option<String> {
+    None
+}
+
+fn default_thumbnail() -> String {
+    "".to_string()
+}
+
+pub fn default_flag() -> Flag {
+    Flag::Unread
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ItemKind {
+    Video,
+    Audio,
+    Other,
+    Magnet,
+    Short,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub channel_url: String,
+    pub channel: String,
+    pub title: String,
+    pub url: String,
+    pub published: String,
+    pub description: String,
+    #[serde(default = "default_thumbnail")]
+    pub thumbnail: String,
+    #[serde(default = "default_flag", deserialize_with = "deserialize_flag")]
+    pub flag: Flag,
+    #[serde(default = "default_content")]
+    pub content: Option<String>,
+    /// date of the refresh which first fetched this item
+    #[serde(default)]
+    pub first_seen: Option<String>,
+    /// canonical identity, see `id::item_id`
+    #[serde(default)]
+    pub id: String,
+    /// length in seconds when the feed tells it
+    #[serde(default)]
+    pub duration: Option<u64>,
+    /// other channels which published the same item, see `cache::collapse_duplicates`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    /// ids of the copies collapsed into this item
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// hidden items are left out of the list
+    #[serde(default)]
+    pub hidden: bool,
+    /// names of the `Tag` rules which matched, see `rules::Rules`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// names of all the rules which matched when the item was fetched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<String>,
+}
+
+macro_rules! get_decendant_node {
+    ( $node:expr, $name:expr  ) => {
+        $node
+            .descendants()
+            .find(|n| n.tag_name().name() == $name)
+            .unwrap_or($node)
+    };
+}
+
+fn enclosure_kind(url: &str, mime_type: Option<&str>) -> ItemKind {
+    if url.starts_with("magnet:") {
+        ItemKind::Magnet
+    } else if mime_type.is_some_and(|t| t.starts_with("video/")) {
+        ItemKind::Video
+    } else {
+        ItemKind::Audio
+    }
+}
+
+/// Parses `3600`, `45:12` or `1:02:03` into seconds
+pub fn parse_duration(s: &str) -> Option<u64> {
+    s.trim().split(':').try_fold(0u64, |acc, part| {
+        let value = part.trim().split('.').next()?.parse::<u64>().ok()?;
+        Some(acc * 60 + value)
+    })
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// `duration` attribute of media:content (PeerTube) or itunes:duration (podcasts)
+fn get_duration(entry: roxmltree::Node) -> Option<u64> {
+    entry
+        .descendants()
+        .find(|n| n.tag_name().name() == "content" && n.attribute("duration").is_some())
+        .and_then(|n| n.attribute("duration"))
+        .or(get_child(entry, "duration").and_then(|n| n.text()))
+        .and_then(parse_duration)
+}
+
+fn get_child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.tag_name().name() == name)
+}
+
+pub fn entry_to_item_atom(title: &str, channel_url: &str, entry: roxmltree::Node) -> Item {
+    let mut kind = ItemKind::Other;
+    let links = entry
+        .children()
+        .filter(|n| n.tag_name().name() == "link")
+        .collect::<Vec<roxmltree::Node>>();
+    let enclosure = links
+        .iter()
+        .find(|n| n.attribute("rel") == Some("enclosure"));
+    let url = match enclosure.and_then(|n| n.attribute("href")) {
+        Some(href) => {
+            kind = enclosure_kind(href, enclosure.and_then(|n| n.attribute("type")));
+            href
+        }
+        None => links
+            .iter()
+            .find(|n| matches!(n.attribute("rel"), None | Some("alternate")))
+            .or(links.first())
+            .and_then(|n| n.attribute("href"))
+            .unwrap_or(""),
+    };
+    let video_title = get_decendant_node!(entry, "title").text().unwrap_or("");
+    let video_published = ["published", "updated", "issued"]
+        .iter()
+        .find_map(|name| get_child(entry, name).and_then(|n| n.text()))
+        .unwrap_or("");
+    let thumbnail = get_decendant_node!(entry, "thumbnail")
+        .attribute("url")
+        .unwrap_or("");
+    if !thumbnail.is_empty() {
+        kind = ItemKind::Video
+    }
+    // youtube puts description and content in media:group, plain atom as direct children
+    let group = get_child(entry, "group").unwrap_or(entry);
+    let description = ["description", "summary"]
+        .iter()
+        .find_map(|name| get_child(group, name).and_then(|n| n.text()))
+        .unwrap_or("");
+    let content = get_child(group, "content")
+        .and_then(|n| n.text())
+        .map(|x| x.to_string());
+    let guid = get_child(entry, "id").and_then(|n| n.text());
+    let duration = get_duration(entry);
+    Item {
+        id: entry_id(url, guid, channel_url, video_title, video_published),
+        kind,
+        content,
+        channel: title.to_string(),
+        title: video_title.to_string(),
+        url: url.to_string(),
+        published: video_published.to_string(),
+        description: description.to_string(),
+        thumbnail: thumbnail.to_string(),
+        flag: default_flag(),
+        channel_url: channel_url.to_string(),
+        first_seen: None,
+        duration,
+        sources: vec![],
+        aliases: vec![],
+        hidden: false,
+        tags: vec![],
+        rules: vec![],
+    }
+}
+
+pub fn entry_to_item_rss(title: &str, channel_url: &str, entry: roxmltree::Node) -> Item {
+    let mut kind = ItemKind::Other;
+    let enclosure = get_decendant_node!(entry, "enclosure");
+    let url = enclosure
+        .attribute("url")
+        .inspect(|x| {
+            kind = enclosure_kind(x, enclosure.attribute("type"));
+        })
+        .unwrap_or(get_decendant_node!(entry, "link").text().unwrap_or(""));
+    let video_title = get_decendant_node!(entry, "title").text().unwrap_or("");
+    let video_published = get_decendant_node!(entry, "pubDate").text().unwrap_or("");
+    let thumbnail = get_decendant_node!(entry, "thumbnail")
+        .attribute("url")
+        .unwrap_or("");
+    if !thumbnail.is_empty() {
+        kind = ItemKind::Video;
+    }
+    let description = get_decendant_node!(entry, "description")
+        .text()
+        .unwrap_or("");
+    let date = match DateTime::parse_from_rfc2822(video_published) {
+        Ok(x) => x.to_rfc3339(),
+        Err(_) => chrono::offset::Local::now().to_rfc3339(),
+    };
+    let content = get_decendant_node!(entry, "encoded")
+        .text()
+        .map(|x| x.to_string());
+    let guid = get_child(entry, "guid").and_then(|n| n.text());
+    let duration = get_duration(entry);
+    Item {
+        id: entry_id(url, guid, channel_url, video_title, video_published),
+        kind,
+        content,
+        channel: title.to_string(),
+        title: video_title.to_string(),
+        url: url.to_string(),
+        published: date,
+        description: description.to_string(),
+        thumbnail: thumbnail.to_string(),
+        flag: default_flag(),
+        channel_url: channel_url.to_string(),
+        first_seen: None,
+        duration,
+        sources: vec![],
+        aliases: vec![],
+        hidden: false,
+        tags: vec![],
+        rules: vec![],
+    }
+}
+
+pub fn entry_to_item_rdf(title: &str, channel_url: &str, entry: roxmltree::Node) -> Item {
+    let url = get_child(entry, "link")
+        .and_then(|n| n.text())
+        .unwrap_or("");
+    let video_title = get_child(entry, "title")
+        .and_then(|n| n.text())
+        .unwrap_or("");
+    let published = get_child(entry, "date").and_then(|n| n.text());
+    let date = match published.map(DateTime::parse_from_rfc3339) {
+        Some(Ok(x)) => x.to_rfc3339(),
+        _ => chrono::offset::Local::now().to_rfc3339(),
+    };
+    let description = get_child(entry, "description")
+        .and_then(|n| n.text())
+        .unwrap_or("");
+    let content = get_child(entry, "encoded")
+        .and_then(|n| n.text())
+        .map(|x| x.to_string());
+    let duration = None;
+    Item {
+        id: entry_id(
+            url,
+            entry.attribute((RDF_NAMESPACE, "about")),
+            channel_url,
+            video_title,
+            published.unwrap_or(""),
+        ),
+        kind: ItemKind::Other,
+        content,
+        channel: title.to_string(),
+        title: video_title.to_string(),
+        url: url.to_string(),
+        published: date,
+        description: description.to_string(),
+        thumbnail: default_thumbnail(),
+        flag: default_flag(),
+        channel_url: channel_url.to_string(),
+        first_seen: None,
+        duration,
+        sources: vec![],
+        aliases: vec![],
+        hidden: false,
+        tags: vec![],
+        rules: vec![],
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonFeed {
+    title: Option<String>,
+    #[serde(default)]
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Deserialize)]
+struct JsonFeedItem {
+    id: Option<serde_json::Value>,
+    url: Option<String>,
+    external_url: Option<String>,
+    title: Option<String>,
+    summary: Option<String>,
+    content_text: Option<String>,
+    content_html: Option<String>,
+    image: Option<String>,
+    banner_image: Option<String>,
+    date_published: Option<String>,
+    date_modified: Option<String>,
+    #[serde(default)]
+    attachments: Vec<JsonFeedAttachment>,
+}
+
+#[derive(Deserialize)]
+struct JsonFeedAttachment {
+    url: String,
+    mime_type: Option<String>,
+    duration_in_seconds: Option<f64>,
+}
+
+fn json_feed_item_to_item(title: &str, channel_url: &str, entry: JsonFeedItem) -> Item {
+    // attachments are the json feed equivalent of rss enclosures
+    let attachment = entry.attachments.into_iter().find(|a| {
+        a.url.starts_with("magnet:")
+            || a.mime_type
+                .as_deref()
+                .is_some_and(|t| t.starts_with("audio/") || t.starts_with("video/"))
+    });
+    let thumbnail = entry.image.or(entry.banner_image).unwrap_or_default();
+    let (kind, url, duration) = match attachment {
+        Some(a) => (
+            enclosure_kind(&a.url, a.mime_type.as_deref()),
+            a.url,
+            a.duration_in_seconds.map(|d| d as u64),
+        ),
+        None => (
+            ItemKind::Other,
+            entry.url.or(entry.external_url).unwrap_or_default(),
+            None,
+        ),
+    };
+    let published = entry.date_published.or(entry.date_modified);
+    let date = match published.as_deref().map(DateTime::parse_from_rfc3339) {
+        Some(Ok(x)) => x.to_rfc3339(),
+        _ => chrono::offset::Local::now().to_rfc3339(),
+    };
+    // ids should be strings but some feeds use numbers
+    let guid = entry.id.map(|id| match id {
+        serde_json::Value::String(s) => s,
+        other => other.to_string(),
+    });
+    let id = entry_id(
+        &url,
+        guid.as_deref(),
+        channel_url,
+        entry.title.as_deref().unwrap_or(""),
+        published.as_deref().unwrap_or(""),
+    );
+    Item {
+        id,
+        kind,
+        content: entry.content_html,
+        channel: title.to_string(),
+        title: entry.title.unwrap_or_default(),
+        url,
+        published: date,
+        description: entry.summary.or(entry.content_text).unwrap_or_default(),
+        thumbnail,
+        flag: default_flag(),
+        channel_url: channel_url.to_string(),
+        first_seen: None,
+        duration,
+        sources: vec![],
+        aliases: vec![],
+        hidden: false,
+        tags: vec![],
+        rules: vec![],
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FeedFormat {
+    /// `<rss>` root with `<channel>` and `<item>` elements (also covers 0.9x)
+    Rss2,
+    /// `<rdf:RDF>` root with `<item>` elements next to `<channel>`
+    Rss1,
+    /// `<feed>` root with `<entry>` elements
+    Atom,
+    /// json document with a jsonfeed.org `version`
+    JsonFeed,
+}
+
+const RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const ATOM_NAMESPACE: &str = "http://www.w3.org/2005/Atom";
+const ATOM_03_NAMESPACE: &str = "http://purl.org/atom/ns#";
+
+#[derive(Debug)]
+pub enum FeedError {
+    Xml(roxmltree::Error),
+    Json(serde_json::Error),
+    MissingChannelTitle,
+    NoEntries,
+    UnknownFormat,
+}
+
+impl std::fmt::Display for FeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FeedError::Xml(e) => write!(f, "failed parsing xml {}", e),
+            FeedError::Json(e) => write!(f, "failed parsing json {}", e),
+            FeedError::MissingChannelTitle => write!(f, "did not find channel title"),
+            FeedError::NoEntries => write!(f, "feed has no entries"),
+            FeedError::UnknownFormat => write!(f, "unknown feed format"),
+        }
+    }
+}
+
+impl std::error::Error for FeedError {}
+
+impl From<roxmltree::Error> for FeedError {
+    fn from(err: roxmltree::Error) -> FeedError {
+        FeedError::Xml(err)
+    }
+}
+
+impl From<serde_json::Error> for FeedError {
+    fn from(err: serde_json::Error) -> FeedError {
+        FeedError::Json(err)
+    }
+}
+
+#[derive(Debug)]
+pub struct ParsedFeed {
+    pub format: FeedFormat,
+    pub title: String,
+    pub items: Vec<Item>,
+}
+
+fn get_title(parent: roxmltree::Node) -> Result<String, FeedError> {
+    get_child(parent, "title")
+        .and_then(|node| node.text())
+        .map(|title| title.trim().to_string())
+        .ok_or(FeedError::MissingChannelTitle)
+}
+
+fn get_atom_videos(feed: roxmltree::Node, channel_url: &str) -> Result<Vec<Item>, FeedError> {
+    let title = get_title(feed)?;
+    Ok(feed
+        .children()
+        .filter(|n| n.tag_name().name() == "entry")
+        .map(|entry| entry_to_item_atom(&title, channel_url, entry))
+        .collect::<Vec<Item>>())
+}
+
+fn get_rss_videos(rss: roxmltree::Node, channel_url: &str) -> Result<Vec<Item>, FeedError> {
+    let channel = get_child(rss, "channel").ok_or(FeedError::UnknownFormat)?;
+    let title = get_title(channel)?;
+    Ok(channel
+        .children()
+        .filter(|n| n.tag_name().name() == "item")
+        .map(|entry| entry_to_item_rss(&title, channel_url, entry))
+        .collect::<Vec<Item>>())
+}
+
+fn get_rdf_videos(rdf: roxmltree::Node, channel_url: &str) -> Result<Vec<Item>, FeedError> {
+    let channel = get_child(rdf, "channel").ok_or(FeedError::UnknownFormat)?;
+    let title = get_title(channel)?;
+    Ok(rdf
+        .children()
+        .filter(|n| n.tag_name().name() == "item")
+        .map(|entry| entry_to_item_rdf(&title, channel_url, entry))
+        .collect::<Vec<Item>>())
+}
+
+fn detect_xml_format(root: roxmltree::Node) -> Option<FeedFormat> {
+    let tag = root.tag_name();
+    match (tag.name(), tag.namespace()) {
+        ("rss", None) => Some(FeedFormat::Rss2),
+        ("RDF", Some(RDF_NAMESPACE)) => Some(FeedFormat::Rss1),
+        // atom 0.3 feeds map the same way
+        ("feed", Some(ATOM_NAMESPACE | ATOM_03_NAMESPACE)) => Some(FeedFormat::Atom),
+        _ => None,
+    }
+}
+
+fn is_json_feed(contents: &str) -> bool {
+    contents.trim_start().starts_with('{')
+        && serde_json::from_str::<serde_json::Value>(contents).is_ok_and(|value| {
+            value["version"]
+                .as_str()
+                .is_some_and(|v| v.starts_with("https://jsonfeed.org/version/"))
+        })
+}
+
+pub fn detect_format(contents: &str) -> Result<FeedFormat, FeedError> {
+    if is_json_feed(contents) {
+        return Ok(FeedFormat::JsonFeed);
+    }
+    let document = roxmltree::Document::parse(contents)?;
+    detect_xml_format(document.root_element()).ok_or(FeedError::UnknownFormat)
+}
+
+/// Parses a JSON Feed 1.0 or 1.1 document, used directly when the server
+/// announces `application/feed+json`
+pub fn parse_json_feed(contents: &str, channel_url: &str) -> Result<ParsedFeed, FeedError> {
+    let feed: JsonFeed = serde_json::from_str(contents)?;
+    let title = feed
+        .title
+        .map(|title| title.trim().to_string())
+        .ok_or(FeedError::MissingChannelTitle)?;
+    let items = feed
+        .items
+        .into_iter()
+        .map(|entry| json_feed_item_to_item(&title, channel_url, entry))
+        .collect::<Vec<Item>>();
+    if items.is_empty() {
+        return Err(FeedError::NoEntries);
+    }
+    Ok(ParsedFeed {
+        format: FeedFormat::JsonFeed,
+        title,
+        items,
+    })
+}
+
+pub fn parse_feed(contents: &str, channel_url: &str) -> Result<ParsedFeed, FeedError> {
+    if is_json_feed(contents) {
+        return parse_json_feed(contents, channel_url);
+    }
+    let document = roxmltree::Document::parse(contents)?;
+    let root = document.root_element();
+    let format = detect_xml_format(root).ok_or(FeedError::UnknownFormat)?;
+    let items = match format {
+        FeedFormat::Atom => get_atom_videos(root, channel_url)?,
+        FeedFormat::Rss2 => get_rss_videos(root, channel_url)?,
+        FeedFormat::Rss1 => get_rdf_videos(root, channel_url)?,
+        FeedFormat::JsonFeed => unreachable!("json feeds are not xml"),
+    };
+    if items.is_empty() {
+        return Err(FeedError::NoEntries);
+    }
+    let title = items[0].channel.clone();
+    Ok(ParsedFeed {
+        format,
+        title,
+        items,
+    })
+}
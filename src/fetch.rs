@@ -0,0 +1,541 @@
+use crate::cache::{
+    merge_state, save_videos, set_short_kinds, ChannelEtags, ChannelStatuses, ItemIndex, Items,
+    ViewMode,
+};
+use crate::config::{replace_home, subscription_manager_relative_path, AppConfig};
+use crate::feed::{parse_feed, parse_json_feed, Item};
+use crate::id::youtube_id;
+use crate::rules::Rules;
+use crate::Logger;
+use chrono::{DateTime, SecondsFormat};
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use percent_encoding::percent_decode;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_ENCODING, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::time::Instant;
+
+pub struct ChanelItems {
+    pub channel_url: String,
+    pub etag: Option<String>,
+    pub videos: Vec<Item>,
+    pub status: ChannelStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum FetchOutcome {
+    Updated { new_items: usize },
+    NotModified,
+    HttpError(u16),
+    ParseError(String),
+    Timeout,
+    Failed(String),
+}
+
+impl std::fmt::Display for FetchOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FetchOutcome::Updated { new_items } => write!(f, "200 ({} new)", new_items),
+            FetchOutcome::NotModified => write!(f, "304 not modified"),
+            FetchOutcome::HttpError(code) => write!(f, "http error {}", code),
+            FetchOutcome::ParseError(e) => write!(f, "parse failure: {}", e),
+            FetchOutcome::Timeout => write!(f, "timeout"),
+            FetchOutcome::Failed(e) => write!(f, "failed: {}", e),
+        }
+    }
+}
+
+/// Outcome of the last fetch of a channel, kept in the cache
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChannelStatus {
+    pub outcome: FetchOutcome,
+    pub retries: u64,
+    pub latency_ms: u64,
+    pub fetched_at: String,
+}
+
+impl ChannelStatus {
+    fn new(outcome: FetchOutcome, retries: u64, start: Instant) -> ChannelStatus {
+        ChannelStatus {
+            outcome,
+            retries,
+            latency_ms: start.elapsed().as_millis() as u64,
+            fetched_at: chrono::offset::Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        matches!(
+            self.outcome,
+            FetchOutcome::Updated { .. } | FetchOutcome::NotModified
+        )
+    }
+}
+
+/// Number of shorts checks running at once
+const SHORT_CHECKS: usize = 8;
+
+/// Whether a YouTube video is a short: its /shorts/ url answers for shorts
+/// and redirects to the watch page for other videos
+async fn check_if_short(client: &reqwest::Client, youtube_id: &str) -> Option<bool> {
+    let url = format!("https://www.youtube.com/shorts/{}", youtube_id);
+    let status = client.head(url.as_str()).send().await.ok()?.status();
+    if status.is_success() {
+        Some(true)
+    } else if status.is_redirection() {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Checks whether the YouTube items published in the last
+/// `short_check_backward_days` days are shorts, each item being checked
+/// once, then sets the kind of the shorts
+pub async fn classify_shorts(app_config: &AppConfig, videos: &mut Items, debug: &Logger<'_>) {
+    let now = chrono::offset::Local::now();
+    let to_check = videos
+        .videos
+        .iter()
+        .filter(|video| !videos.shorts.contains_key(&video.id))
+        .filter(|video| {
+            DateTime::parse_from_rfc3339(&video.published).is_ok_and(|published| {
+                now.signed_duration_since(published).num_days()
+                    <= app_config.short_check_backward_days as i64
+            })
+        })
+        .filter_map(|video| youtube_id(&video.url).map(|id| (video.id.clone(), id.to_string())))
+        .collect::<HashMap<String, String>>();
+    if !to_check.is_empty() {
+        let client = reqwest::Client::builder()
+            .use_rustls_tls()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(std::time::Duration::from_secs(10))
+            .build();
+        match client {
+            Ok(client) => {
+                debug(&format!("checking {} videos for shorts...", to_check.len()));
+                let checks = stream::iter(to_check)
+                    .map(|(id, youtube_id)| {
+                        let client = &client;
+                        async move { (id, check_if_short(client, &youtube_id).await) }
+                    })
+                    .buffer_unordered(SHORT_CHECKS)
+                    .collect::<Vec<(String, Option<bool>)>>()
+                    .await;
+                // failed checks are tried again on the next refresh
+                for (id, short) in checks {
+                    if let Some(short) = short {
+                        videos.shorts.insert(id, short);
+                    }
+                }
+            }
+            Err(e) => debug(&format!("🔴 failed checking shorts: {}", e)),
+        }
+    }
+    set_short_kinds(videos);
+}
+
+fn get_original_channel_videos(
+    channel_url: &String,
+    channel_etag: &Option<&String>,
+    original_videos: &Items,
+    status: ChannelStatus,
+) -> ChanelItems {
+    let mut channel_videos: Vec<Item> = vec![];
+    for video in original_videos.videos.iter() {
+        if &video.channel_url == channel_url {
+            channel_videos.push(video.clone())
+        }
+    }
+    ChanelItems {
+        channel_url: channel_url.to_string(),
+        etag: channel_etag.map(|x| x.to_string()),
+        videos: channel_videos,
+        status,
+    }
+}
+
+fn root_cause(e: &reqwest::Error) -> String {
+    let mut cause: &dyn std::error::Error = e;
+    while let Some(source) = cause.source() {
+        cause = source;
+    }
+    cause.to_string()
+}
+
+fn count_new_items(channel_url: &str, videos: &[Item], original_videos: &Items) -> usize {
+    let known = ItemIndex::new(
+        original_videos
+            .videos
+            .iter()
+            .filter(|video| video.channel_url == channel_url),
+    );
+    videos
+        .iter()
+        .filter(|video| known.get(video).is_none())
+        .count()
+}
+
+fn get_headers(channel_etag: Option<&String>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("*/*"));
+    if let Some(etag) = channel_etag {
+        if let Ok(s) = HeaderValue::from_str(etag.as_str()) {
+            headers.insert(IF_NONE_MATCH, s);
+        }
+    }
+    headers
+}
+
+#[derive(Debug)]
+pub struct ChannelURLWithBasicAuth {
+    pub channel_url: String,
+    pub password: Option<String>,
+    pub user: Option<String>,
+}
+
+pub fn parse_basic_auth(channel_url: &str) -> ChannelURLWithBasicAuth {
+    match Regex::new(r"^(https://)([^:/]*):([^@/]*)@(.*)$") {
+        Ok(re) => match re.captures(channel_url) {
+            Some(caps) => ChannelURLWithBasicAuth {
+                channel_url: (format!("{}{}", &caps[1], &caps[4])).to_string(),
+                password: Some(
+                    percent_decode(caps[3].as_bytes())
+                        .decode_utf8_lossy()
+                        .to_string(),
+                ),
+                user: Some(
+                    percent_decode(caps[2].as_bytes())
+                        .decode_utf8_lossy()
+                        .to_string(),
+                ),
+            },
+            None => ChannelURLWithBasicAuth {
+                channel_url: channel_url.to_string(),
+                password: None,
+                user: None,
+            },
+        },
+        Err(_) => ChannelURLWithBasicAuth {
+            channel_url: channel_url.to_string(),
+            password: None,
+            user: None,
+        },
+    }
+}
+
+pub fn build_request(
+    channel_url: &str,
+    client: &reqwest::Client,
+    channel_etag: Option<&String>,
+) -> reqwest::RequestBuilder {
+    let channel_url_with_basic_auth = parse_basic_auth(channel_url);
+    match channel_url_with_basic_auth.user {
+        Some(user) => client
+            .get(channel_url_with_basic_auth.channel_url.as_str())
+            .headers(get_headers(channel_etag))
+            .basic_auth(user, channel_url_with_basic_auth.password),
+        None => client
+            .get(channel_url_with_basic_auth.channel_url.as_str())
+            .headers(get_headers(channel_etag)),
+    }
+}
+
+pub fn get_subscriptions_xml(debug: &Logger<'_>) -> Result<String, std::io::Error> {
+    match dirs::home_dir() {
+        Some(home) => match home.to_str() {
+            Some(s) => {
+                let path = format!("{}/{}", s, subscription_manager_relative_path());
+                if fs::metadata(&path).is_ok() {
+                    fs::read_to_string(path)
+                } else {
+                    Ok("<opml></opml>".to_string())
+                }
+            }
+            None => {
+                debug("failed conversting home to str");
+                Ok("<opml></opml>".to_string())
+            }
+        },
+        None => {
+            debug("failed finding home dir");
+            Ok("<opml></opml>".to_string())
+        }
+    }
+}
+
+pub async fn get_videos(
+    xml: String,
+    additional_channel_ids: &[String],
+    additional_channel_urls: &[String],
+    original_videos: &Items,
+    rules: &Rules,
+    client: &reqwest::Client,
+    debug: &Logger<'_>,
+) -> Vec<ChanelItems> {
+    match roxmltree::Document::parse(xml.as_str()) {
+        Ok(document) => {
+            let mut urls_from_xml: Vec<String> = document
+                .descendants()
+                .filter(|n| n.tag_name().name() == "outline")
+                .filter_map(|entry| entry.attribute("xmlUrl"))
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>();
+            let urls_from_additional = additional_channel_ids.iter().map(|id| {
+                // http is faster than https
+                "http://www.youtube.com/feeds/videos.xml?channel_id=".to_string() + id
+            });
+            let urls_from_additional_2 = additional_channel_urls.iter().map(|url| url.to_string());
+            urls_from_xml.extend(urls_from_additional);
+            urls_from_xml.extend(urls_from_additional_2);
+            let futs: Vec<_> = urls_from_xml
+                .iter()
+                .map(|url| {
+                    let etag = match original_videos.channel_etags.get(&url.to_string()) {
+                        Some(Some(string)) => Some(string),
+                        _ => None,
+                    };
+                    get_channel_videos(client, url.to_string(), etag, original_videos, rules, debug)
+                })
+                .collect();
+            join_all(futs).await
+        }
+        Err(e) => {
+            debug(&format!("failed parsing xml {}", e));
+            vec![]
+        }
+    }
+}
+
+pub async fn get_channel_videos(
+    client: &reqwest::Client,
+    channel_url: String,
+    channel_etag: Option<&String>,
+    original_videos: &Items,
+    rules: &Rules,
+    debug: &Logger<'_>,
+) -> ChanelItems {
+    let max_tries = 5;
+    let start = Instant::now();
+    let mut outcome = FetchOutcome::Failed("not fetched".to_string());
+    for i in 0..max_tries {
+        let request = build_request(&channel_url, client, channel_etag);
+        let wrapped_response: Result<reqwest::Response, reqwest::Error> = request.send().await;
+        match wrapped_response {
+            Ok(response) => {
+                let status = response.status();
+                if status.as_u16() == 304 {
+                    return get_original_channel_videos(
+                        &channel_url,
+                        &channel_etag,
+                        original_videos,
+                        ChannelStatus::new(FetchOutcome::NotModified, i, start),
+                    );
+                } else if status.is_success() {
+                    debug(&format!("💚 success loading {}", &channel_url));
+                    let headers = response.headers();
+                    let etag_opt_opt = headers
+                        .get(ETAG)
+                        .map(|x| x.to_str().ok().map(|y| y.to_string()));
+                    let json_feed = headers
+                        .get(CONTENT_TYPE)
+                        .and_then(|x| x.to_str().ok())
+                        .is_some_and(|x| x.starts_with("application/feed+json"));
+                    match response.text().await {
+                        Ok(text) => {
+                            let parsed = if json_feed {
+                                parse_json_feed(&text, &channel_url)
+                            } else {
+                                parse_feed(&text, &channel_url)
+                            };
+                            return match parsed {
+                                Ok(mut parsed) => {
+                                    parsed.items.iter_mut().for_each(|video| rules.apply(video));
+                                    let new_items = count_new_items(
+                                        &channel_url,
+                                        &parsed.items,
+                                        original_videos,
+                                    );
+                                    ChanelItems {
+                                        channel_url: channel_url.to_string(),
+                                        etag: match etag_opt_opt {
+                                            Some(Some(x)) => Some(x),
+                                            _ => None,
+                                        },
+                                        videos: parsed.items,
+                                        status: ChannelStatus::new(
+                                            FetchOutcome::Updated { new_items },
+                                            i,
+                                            start,
+                                        ),
+                                    }
+                                }
+                                Err(e) => {
+                                    debug(&format!("🔴 failed parsing {}: {}", &channel_url, e));
+                                    get_original_channel_videos(
+                                        &channel_url,
+                                        &None,
+                                        original_videos,
+                                        ChannelStatus::new(
+                                            FetchOutcome::ParseError(e.to_string()),
+                                            i,
+                                            start,
+                                        ),
+                                    )
+                                }
+                            };
+                        }
+                        Err(e) => outcome = FetchOutcome::Failed(e.to_string()),
+                    }
+                } else {
+                    outcome = FetchOutcome::HttpError(status.as_u16());
+                }
+            }
+            Err(e) => {
+                outcome = if e.is_timeout() {
+                    FetchOutcome::Timeout
+                } else {
+                    FetchOutcome::Failed(root_cause(&e))
+                };
+                if i < max_tries - 1 {
+                    debug(&format!(
+                        "🟠 retrying after fail #{} for {}: {:?}",
+                        i,
+                        &channel_url,
+                        e.status()
+                    ));
+                }
+            }
+        }
+        let dur = std::time::Duration::from_millis(i * 100);
+        std::thread::sleep(dur);
+    }
+    debug(&format!("🔴 failed loading {}: {}", &channel_url, outcome));
+    // keeping the cached items keeps their flags, queue entries and first seen dates
+    get_original_channel_videos(
+        &channel_url,
+        &None,
+        original_videos,
+        ChannelStatus::new(outcome, max_tries - 1, start),
+    )
+}
+
+pub fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .use_rustls_tls()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .unwrap()
+}
+
+/// Fetches one channel, such as a channel just subscribed to, and saves its
+/// items with the cached ones
+pub async fn refresh_channel(
+    app_config: &AppConfig,
+    original_videos: &Items,
+    channel_url: &str,
+    debug: &Logger<'_>,
+) -> Items {
+    let res = get_channel_videos(
+        &client(),
+        channel_url.to_string(),
+        None,
+        original_videos,
+        &Rules::new(&app_config.rules, debug),
+        debug,
+    )
+    .await;
+    let mut videos = Items {
+        videos: original_videos
+            .videos
+            .iter()
+            .filter(|video| video.channel_url != channel_url)
+            .cloned()
+            .chain(res.videos)
+            .collect(),
+        channel_etags: original_videos.channel_etags.clone(),
+        channel_statuses: original_videos.channel_statuses.clone(),
+        ..Default::default()
+    };
+    videos
+        .channel_etags
+        .insert(channel_url.to_string(), res.etag);
+    videos
+        .channel_statuses
+        .insert(channel_url.to_string(), res.status);
+    merge_state(
+        &mut videos,
+        original_videos,
+        &chrono::offset::Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+    );
+    // the items of the other channels stay new until the next full refresh
+    videos.last_refresh = original_videos.last_refresh.clone();
+    classify_shorts(app_config, &mut videos, debug).await;
+    if let Err(e) = save_videos(app_config, &videos) {
+        debug(&format!(
+            "failed writing {} {}",
+            replace_home(&app_config.cache_path),
+            e
+        ));
+    }
+    videos
+}
+
+pub async fn refresh(
+    app_config: &AppConfig,
+    original_videos: &Items,
+    debug: &Logger<'_>,
+) -> Option<Items> {
+    match get_subscriptions_xml(debug) {
+        Ok(xml) => {
+            let mut etags: ChannelEtags = HashMap::new();
+            let mut statuses: ChannelStatuses = HashMap::new();
+            let client = client();
+            let vids = get_videos(
+                xml,
+                &app_config.channel_ids,
+                &app_config.channel_urls,
+                original_videos,
+                &Rules::new(&app_config.rules, debug),
+                &client,
+                debug,
+            )
+            .await
+            .into_iter()
+            .flat_map(|res| {
+                etags.insert(res.channel_url.clone(), res.etag.clone());
+                statuses.insert(res.channel_url.clone(), res.status);
+                res.videos
+            })
+            .collect::<Vec<Item>>();
+            let mut videos = Items {
+                channel_etags: etags,
+                channel_statuses: statuses,
+                last_refresh: None,
+                queue: vec![],
+                view_mode: ViewMode::All,
+                shorts: HashMap::new(),
+                videos: vids,
+            };
+            merge_state(
+                &mut videos,
+                original_videos,
+                &chrono::offset::Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+            );
+            classify_shorts(app_config, &mut videos, debug).await;
+            if let Err(e) = save_videos(app_config, &videos) {
+                debug(&format!(
+                    "failed writing {} {}",
+                    replace_home(&app_config.cache_path),
+                    e
+                ));
+            }
+            Some(videos)
+        }
+        Err(_) => None,
+    }
+}
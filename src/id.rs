@@ -0,0 +1,133 @@
+use regex::Regex;
+use reqwest::Url;
+use std::sync::LazyLock;
+
+static YOUTUBE_ID: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?:[?&]v=|youtu\.be/|/shorts/|/embed/|/live/|^yt:video:)([0-9A-Za-z_-]{11})(?:[^0-9A-Za-z_-]|$)",
+    )
+    .unwrap()
+});
+
+static PEERTUBE_UUID: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"/videos/(?:watch|embed)/([0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12})",
+    )
+    .unwrap()
+});
+
+/// `/w/` urls of PeerTube, the short uuid being all of the path
+static PEERTUBE_SHORT_UUID: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^https?://[^/?#]+/w/([1-9A-HJ-NP-Za-km-z]{21,22})(?:[?#]|$)").unwrap()
+});
+
+/// Alphabet of the short-uuid package used by PeerTube for its `/w/` urls
+const FLICKR_BASE58: &str = "123456789abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ";
+
+const TRACKING_PARAMS: [&str; 5] = ["fbclid", "gclid", "mc_cid", "mc_eid", "ref"];
+
+pub fn youtube_id(s: &str) -> Option<&str> {
+    YOUTUBE_ID
+        .captures(s)
+        .and_then(|caps| caps.get(1))
+        .map(|id| id.as_str())
+}
+
+fn short_uuid_to_uuid(short: &str) -> Option<String> {
+    let value = short.chars().try_fold(0u128, |acc, c| {
+        let digit = FLICKR_BASE58.find(c)? as u128;
+        acc.checked_mul(58)?.checked_add(digit)
+    })?;
+    let hex = format!("{:032x}", value);
+    // PeerTube uuids are random (version 4) ones, other `/w/` paths are not
+    let variant = hex.as_bytes()[16];
+    if &hex[12..13] != "4" || !matches!(variant, b'8' | b'9' | b'a' | b'b') {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    ))
+}
+
+pub fn peertube_uuid(s: &str) -> Option<String> {
+    if let Some(caps) = PEERTUBE_UUID.captures(s) {
+        return Some(caps[1].to_string());
+    }
+    PEERTUBE_SHORT_UUID
+        .captures(s)
+        .and_then(|caps| short_uuid_to_uuid(&caps[1]))
+}
+
+/// Url without scheme, `www.`, fragment, trailing slash and tracking
+/// parameters, so that http/https or utm variants of a link compare equal
+pub fn normalize_url(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let query = parsed
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join("&");
+    let port = parsed.port().map(|p| format!(":{}", p)).unwrap_or_default();
+    let path = parsed.path().trim_end_matches('/');
+    Some(if query.is_empty() {
+        format!("{}{}{}", host, port, path)
+    } else {
+        format!("{}{}{}?{}", host, port, path, query)
+    })
+}
+
+/// Canonical identity of an item: the YouTube video id, the PeerTube video
+/// uuid, the feed guid or the normalized url, in this order of preference.
+/// Used to merge flags across refreshes and to spot duplicates.
+pub fn item_id(url: &str, guid: Option<&str>) -> String {
+    let guid = guid.map(str::trim).filter(|guid| !guid.is_empty());
+    let candidates = [Some(url), guid];
+    if let Some(id) = candidates.iter().flatten().find_map(|s| youtube_id(s)) {
+        return format!("youtube:{}", id);
+    }
+    if let Some(uuid) = candidates.iter().flatten().find_map(|s| peertube_uuid(s)) {
+        return format!("peertube:{}", uuid);
+    }
+    match guid {
+        Some(guid) => match normalize_url(guid) {
+            Some(normalized) => format!("url:{}", normalized),
+            None => format!("guid:{}", guid),
+        },
+        None => match normalize_url(url) {
+            Some(normalized) => format!("url:{}", normalized),
+            None => format!("url:{}", url),
+        },
+    }
+}
+
+/// Id of a feed entry, see `item_id`. Entries with neither url nor guid
+/// would all share the same id, they get one from their channel, title and
+/// publication date instead.
+pub fn entry_id(
+    url: &str,
+    guid: Option<&str>,
+    channel_url: &str,
+    title: &str,
+    published: &str,
+) -> String {
+    let guid = guid.filter(|guid| !guid.trim().is_empty());
+    if !url.trim().is_empty() || guid.is_some() {
+        return item_id(url, guid);
+    }
+    // FNV-1a, ids being kept in the cache across versions
+    let hash = [channel_url, title, published]
+        .iter()
+        .flat_map(|field| field.bytes().chain(std::iter::once(0)))
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    format!("hash:{:016x}", hash)
+}
@@ -0,0 +1,35 @@
+extern crate base64;
+extern crate blockish_player;
+extern crate chrono;
+extern crate crossterm;
+extern crate crossterm_input;
+extern crate dirs;
+extern crate percent_encoding;
+extern crate reqwest;
+extern crate roxmltree;
+extern crate serde;
+
+pub mod cache;
+pub mod config;
+pub mod feed;
+pub mod fetch;
+pub mod id;
+pub mod playback;
+pub mod query;
+pub mod resolve;
+pub mod rules;
+pub mod search;
+pub mod term;
+
+/// Sink for progress and error messages, the terminal UI prints them on its
+/// bottom line while headless commands print them on stderr (`refresh` on
+/// stdout, as `--background` did).
+pub type Logger<'a> = dyn Fn(&str) + Sync + 'a;
+
+pub fn notify(msg: &str) {
+    let _ = notify_rust::Notification::new()
+        .summary("youtube-subscriptions")
+        .body(msg)
+        .icon("computer")
+        .show();
+}
@@ -0,0 +1,454 @@
+use crate::cache::{load_cache, save_videos, Items};
+use crate::config::AppConfig;
+use crate::feed::{format_duration, Flag, Item, ItemKind};
+use crate::term::{clear, flush_stdout, print_press_any_key_and_pause};
+use crate::Logger;
+use base64::{engine::general_purpose, Engine as _};
+use futures::channel::mpsc::{self, UnboundedReceiver};
+use std::fs;
+use std::io::ErrorKind::NotFound;
+use std::io::{BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use utf8::BufReadDecoder;
+
+pub fn play_video_usual(path: &str, app_config: &AppConfig, debug: &Logger<'_>) {
+    for player in &app_config.players {
+        if fs::metadata(&player[0]).is_ok() {
+            let mut child1 = Command::new(&player[0]);
+            for arg in player.iter().skip(1) {
+                child1.arg(arg);
+            }
+            read_command_output(child1.arg(path), &player[0], debug);
+            return;
+        }
+    }
+}
+
+pub fn play_video(path: &str, app_config: &AppConfig, debug: &Logger<'_>) {
+    match &app_config.blockish_player {
+        None => play_video_usual(path, app_config, debug),
+        Some(player) => {
+            match blockish_player::video_command(player, &path.to_string()) {
+                Ok(mut command) => {
+                    read_command_output(&mut command, "blockish_player", debug);
+                }
+                Err(e) => {
+                    debug(&format!("error: {:?}", e));
+                    play_video_usual(path, app_config, debug);
+                }
+            };
+        }
+    }
+}
+
+/// File in `video_path` where youtube-dl stores the video of an url, named
+/// after its url safe base64 encoding. Earlier versions named it after the
+/// url decoded as base64, such files being still used when they exist.
+pub fn download_path(url: &str, app_config: &AppConfig) -> String {
+    let path = |name: &str| {
+        format!(
+            "{}/{}.{}",
+            app_config.video_path, name, app_config.video_extension
+        )
+    };
+    general_purpose::STANDARD_NO_PAD
+        .decode(url)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .map(|name| path(&name))
+        .filter(|legacy| fs::metadata(legacy).is_ok())
+        .unwrap_or_else(|| path(&general_purpose::URL_SAFE_NO_PAD.encode(url)))
+}
+
+/// Downloads an item to `video_path` with youtube-dl, so that it can be
+/// played later without waiting
+pub fn download(v: &Item, app_config: &AppConfig, debug: &Logger<'_>) {
+    let app_config = &app_config.for_channel(&v.channel_url);
+    match v.kind {
+        ItemKind::Magnet => open_magnet(&v.url, app_config, debug),
+        _ => download_video(
+            &download_path(&v.url, app_config),
+            &v.url,
+            app_config,
+            debug,
+        ),
+    }
+}
+
+pub fn download_video(path: &str, id: &str, app_config: &AppConfig, debug: &Logger<'_>) {
+    if fs::metadata(path).is_err() {
+        read_command_output(
+            Command::new("youtube-dl")
+                .arg("-f")
+                .arg(&app_config.youtubedl_format)
+                .arg("-o")
+                .arg(path)
+                .arg("--")
+                .arg(id),
+            "youtube-dl",
+            debug,
+        )
+    }
+}
+
+pub fn open_magnet(url: &str, app_config: &AppConfig, debug: &Logger<'_>) {
+    if let Some(open_magnet) = &app_config.open_magnet {
+        read_command_output(Command::new(open_magnet).arg(url), open_magnet, debug)
+    }
+}
+
+pub fn read_command_output(command: &mut Command, binary: &str, debug: &Logger<'_>) {
+    match command.stdout(Stdio::piped()).spawn() {
+        Ok(mut child) => {
+            let stdout_option = child.stdout.take();
+            match stdout_option {
+                Some(stdout) => {
+                    let mut decoder = BufReadDecoder::new(BufReader::new(stdout));
+                    loop {
+                        let read_result = decoder.next_strict();
+                        match read_result {
+                            Some(Ok(s)) => {
+                                print!("{}", s);
+                            }
+                            Some(Err(_)) => {}
+                            None => {
+                                break;
+                            }
+                        }
+                    }
+                }
+                None => debug("no stdout"),
+            }
+            let stderr_option = child.stderr.take();
+            match stderr_option {
+                Some(stderr) => {
+                    for byte in BufReader::new(stderr).bytes().flatten() {
+                        print!("{}", byte as char);
+                        flush_stdout();
+                    }
+                }
+                None => debug("no stderr"),
+            }
+            match child.wait() {
+                Ok(status) => {
+                    if !status.success() {
+                        println!(
+                            "error while running {:?}, return status: {:?}",
+                            command,
+                            status.code()
+                        );
+                        print_press_any_key_and_pause()
+                    }
+                }
+                Err(e) => {
+                    println!("error while running {:?}, error: {:?}", command, e);
+                    print_press_any_key_and_pause()
+                }
+            }
+        }
+        Err(e) => {
+            if let NotFound = e.kind() {
+                println!("`{}` was not found: maybe you should install it ?", binary)
+            } else {
+                println!("error while runnnig {} : {}", binary, e);
+            }
+            print_press_any_key_and_pause()
+        }
+    }
+}
+
+/// Number of mpv instances started, so that each gets its own ipc socket
+static PLAYBACKS: AtomicUsize = AtomicUsize::new(0);
+
+/// Position reached in an item when its player exited
+#[derive(Debug, Clone)]
+pub struct PlaybackProgress {
+    /// ids of the item and of its collapsed copies
+    pub ids: Vec<String>,
+    pub position: u64,
+    pub duration: Option<u64>,
+}
+
+impl PlaybackProgress {
+    /// Whether most of the item has been seen, as per `watched_percent`
+    pub fn finished(&self, app_config: &AppConfig) -> bool {
+        self.duration
+            .is_some_and(|d| d > 0 && self.position * 100 >= d * app_config.watched_percent)
+    }
+}
+
+/// Flag of an item after playing it up to `position`: watched once most of
+/// it has been seen, in progress otherwise. Starred items stay starred.
+pub fn progress_flag(previous: &Flag, progress: &PlaybackProgress, app_config: &AppConfig) -> Flag {
+    match previous {
+        Flag::Starred => Flag::Starred,
+        _ if progress.finished(app_config) => Flag::Watched,
+        // a few seconds is not worth resuming
+        _ if progress.position >= 5 => Flag::InProgress {
+            position: progress.position,
+        },
+        previous => previous.clone(),
+    }
+}
+
+/// Stores the position and duration reported by the player on all copies of
+/// the item, and takes it out of the queue once finished
+pub fn record_progress(videos: &mut Items, progress: &PlaybackProgress, app_config: &AppConfig) {
+    for video in videos
+        .videos
+        .iter_mut()
+        .filter(|v| progress.ids.contains(&v.id))
+    {
+        video.flag = progress_flag(&video.flag, progress, app_config);
+        if progress.duration.is_some() {
+            video.duration = progress.duration;
+        }
+    }
+    if progress.finished(app_config) {
+        videos.queue.retain(|id| !progress.ids.contains(id));
+    }
+}
+
+/// Records progress in the cache, which may have been refreshed while
+/// playing, `videos` becoming what was saved
+pub fn save_progress(
+    videos: &mut Items,
+    progress: &PlaybackProgress,
+    app_config: &AppConfig,
+) -> std::io::Result<()> {
+    if let Ok(cached) = load_cache(app_config) {
+        *videos = cached;
+    }
+    record_progress(videos, progress, app_config);
+    save_videos(app_config, videos)
+}
+
+fn mpv_usable(app_config: &AppConfig) -> bool {
+    app_config.mpv_mode && fs::metadata(&app_config.mpv_path).is_ok()
+}
+
+fn mpv_command(url: &str, app_config: &AppConfig, no_video: bool) -> Command {
+    let mut command = Command::new(&app_config.mpv_path);
+    command
+        .args(&app_config.player_additional_opts)
+        .arg(if no_video { "--no-video" } else { "" })
+        .arg(if app_config.fs { "-fs" } else { "" })
+        .arg("--ytdl-format=".to_owned() + &app_config.youtubedl_format)
+        .arg("--no-terminal")
+        .arg(url);
+    command
+}
+
+/// mpv started with an ipc socket to follow the position in an item
+struct MpvPlayback {
+    child: Child,
+    socket: String,
+    start: u64,
+    ids: Vec<String>,
+}
+
+impl MpvPlayback {
+    fn spawn(v: &Item, app_config: &AppConfig, no_video: bool) -> std::io::Result<MpvPlayback> {
+        let start = match v.flag {
+            Flag::InProgress { position } => position,
+            _ => 0,
+        };
+        let socket = format!(
+            "{}/youtube-subscriptions-mpv-{}-{}.sock",
+            std::env::temp_dir().display(),
+            std::process::id(),
+            PLAYBACKS.fetch_add(1, Ordering::Relaxed)
+        );
+        let _ = fs::remove_file(&socket);
+        let no_video = no_video
+            || app_config
+                .channel_settings(&v.channel_url)
+                .is_some_and(|settings| matches!(settings.kind, Some(ItemKind::Audio)));
+        let mut command = mpv_command(&v.url, &app_config.for_channel(&v.channel_url), no_video);
+        command.arg(format!("--input-ipc-server={}", socket));
+        if start > 0 {
+            command.arg(format!("--start={}", start));
+        }
+        let child = command.stdin(Stdio::null()).spawn()?;
+        Ok(MpvPlayback {
+            child,
+            socket,
+            start,
+            ids: std::iter::once(v.id.clone())
+                .chain(v.aliases.iter().cloned())
+                .collect(),
+        })
+    }
+
+    fn wait(self) -> PlaybackProgress {
+        let (position, duration) = watch_mpv(self.child, &self.socket, self.start);
+        let _ = fs::remove_file(&self.socket);
+        PlaybackProgress {
+            ids: self.ids,
+            position,
+            duration,
+        }
+    }
+}
+
+fn playing_message(v: &Item) -> String {
+    match v.flag {
+        Flag::InProgress { position } if position > 0 => format!(
+            "resuming {} at {} with mpv...",
+            v.url,
+            format_duration(position)
+        ),
+        _ => format!("playing {} with mpv...", v.url),
+    }
+}
+
+/// Plays an item, resuming it if it is in progress. With mpv, the returned
+/// receiver gets the position reached once mpv exits.
+pub fn play(
+    v: &Item,
+    app_config: &AppConfig,
+    no_video: bool,
+    debug: &Logger<'_>,
+) -> Option<UnboundedReceiver<PlaybackProgress>> {
+    play_queue(std::slice::from_ref(v), app_config, no_video, debug)
+}
+
+/// Plays items one after the other, resuming the ones in progress. With mpv,
+/// the returned receiver gets the position reached in each item, and the
+/// queue stops when mpv is quit before the end of an item. Other players
+/// are run in turn before returning.
+pub fn play_queue(
+    items: &[Item],
+    app_config: &AppConfig,
+    no_video: bool,
+    debug: &Logger<'_>,
+) -> Option<UnboundedReceiver<PlaybackProgress>> {
+    if !mpv_usable(app_config) {
+        for v in items {
+            play_url(
+                &v.url,
+                &v.kind,
+                &app_config.for_channel(&v.channel_url),
+                no_video,
+                debug,
+            );
+        }
+        return None;
+    }
+    let first = items.first()?;
+    debug(&playing_message(first));
+    let playback = match MpvPlayback::spawn(first, app_config, no_video) {
+        Ok(playback) => playback,
+        Err(e) => {
+            debug(&format!("failed starting mpv: {}", e));
+            return None;
+        }
+    };
+    let (sender, receiver) = mpsc::unbounded();
+    let next = items[1..].to_vec();
+    let app_config = app_config.clone();
+    std::thread::spawn(move || {
+        let mut playback = playback;
+        let mut next = next.into_iter();
+        loop {
+            let progress = playback.wait();
+            let finished = progress.finished(&app_config);
+            if sender.unbounded_send(progress).is_err() || !finished {
+                break;
+            }
+            match next
+                .next()
+                .map(|v| MpvPlayback::spawn(&v, &app_config, no_video))
+            {
+                Some(Ok(started)) => playback = started,
+                _ => break,
+            }
+        }
+    });
+    Some(receiver)
+}
+
+/// Follows time-pos and duration on mpv ipc socket until mpv exits, returns
+/// the last position and the duration
+#[cfg(unix)]
+fn watch_mpv(mut child: Child, socket: &str, start: u64) -> (u64, Option<u64>) {
+    use std::io::{BufRead, Write};
+    use std::os::unix::net::UnixStream;
+    let mut position = start as f64;
+    let mut duration: Option<f64> = None;
+    // mpv creates the socket shortly after starting
+    let stream = (0..50).find_map(|_| match UnixStream::connect(socket) {
+        Ok(stream) => Some(stream),
+        Err(_) => {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            None
+        }
+    });
+    if let Some(mut stream) = stream {
+        let _ = stream.write_all(
+            b"{\"command\": [\"observe_property\", 1, \"time-pos\"]}\n\
+              {\"command\": [\"observe_property\", 2, \"duration\"]}\n",
+        );
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) else {
+                continue;
+            };
+            match (message["event"].as_str(), message["name"].as_str()) {
+                (Some("property-change"), Some("time-pos")) => {
+                    if let Some(value) = message["data"].as_f64() {
+                        position = value;
+                    }
+                }
+                (Some("property-change"), Some("duration")) => {
+                    if let Some(value) = message["data"].as_f64() {
+                        duration = Some(value);
+                    }
+                }
+                (Some("end-file"), _) if message["reason"].as_str() == Some("eof") => {
+                    if let Some(duration) = duration {
+                        position = duration;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    let _ = child.wait();
+    (position as u64, duration.map(|d| d as u64))
+}
+
+#[cfg(not(unix))]
+fn watch_mpv(mut child: Child, _socket: &str, start: u64) -> (u64, Option<u64>) {
+    let _ = child.wait();
+    (start, None)
+}
+
+pub fn play_url(
+    url: &str,
+    kind: &ItemKind,
+    app_config: &AppConfig,
+    no_video: bool,
+    debug: &Logger<'_>,
+) {
+    if mpv_usable(app_config) {
+        let message = format!("playing {} with mpv...", url);
+        debug(&message);
+        let _ = mpv_command(url, app_config, no_video).spawn();
+    } else {
+        clear();
+        match kind {
+            ItemKind::Audio => {
+                play_video(url, app_config, debug);
+            }
+            ItemKind::Magnet => {
+                open_magnet(url, app_config, debug);
+            }
+            _ => {
+                let path = download_path(url, app_config);
+                download_video(&path, url, app_config, debug);
+                play_video(&path, app_config, debug);
+            }
+        }
+    }
+}
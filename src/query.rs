@@ -0,0 +1,269 @@
+use crate::cache::filter_regex;
+use crate::feed::{parse_duration, Flag, Item};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, TimeZone};
+use regex::Regex;
+use std::cmp::Ordering;
+
+/// Filter typed with `f`, such as
+/// `channel:linus title:"review" kind:audio after:7d duration:<10m -flag:read tag:live`.
+/// All terms have to match, `-` negates a term and bare words are fuzzy
+/// matched against the kind, channel and title.
+#[derive(Debug, Clone)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    negated: bool,
+    predicate: Predicate,
+}
+
+#[derive(Debug, Clone)]
+enum Predicate {
+    Channel(String),
+    Title(String),
+    Kind(String),
+    Tag(String),
+    Flag(Flag),
+    After(DateTime<FixedOffset>),
+    Before(DateTime<FixedOffset>),
+    Duration(Ordering, bool, u64),
+    Fuzzy(Regex),
+}
+
+#[derive(Debug)]
+pub enum QueryError {
+    InvalidValue(String, String),
+    UnclosedQuote,
+    Regex(regex::Error),
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            QueryError::InvalidValue(field, value) => {
+                write!(f, "invalid value {:?} for {}", value, field)
+            }
+            QueryError::UnclosedQuote => write!(f, "missing closing quote"),
+            QueryError::Regex(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl From<regex::Error> for QueryError {
+    fn from(err: regex::Error) -> QueryError {
+        QueryError::Regex(err)
+    }
+}
+
+/// Splits on whitespace outside of double quotes, quotes being removed
+fn tokenize(s: &str) -> Result<Vec<String>, QueryError> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut quoted = false;
+    let mut started = false;
+    for c in s.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    tokens.push(std::mem::take(&mut token));
+                    started = false;
+                }
+            }
+            c => {
+                token.push(c);
+                started = true;
+            }
+        }
+    }
+    if quoted {
+        return Err(QueryError::UnclosedQuote);
+    }
+    if started {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Duration such as `90`, `90s`, `10m`, `1h30m` or `1:30:00`, in seconds
+fn parse_length(s: &str) -> Option<u64> {
+    if s.contains(':') || s.chars().all(|c| c.is_ascii_digit()) {
+        return parse_duration(s);
+    }
+    let mut total = 0;
+    let mut number = String::new();
+    for c in s.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' | 's' => {
+                let value = std::mem::take(&mut number).parse::<u64>().ok()?;
+                total += value
+                    * match c {
+                        'h' => 3600,
+                        'm' => 60,
+                        _ => 1,
+                    };
+            }
+            _ => return None,
+        }
+    }
+    number.is_empty().then_some(total)
+}
+
+/// Date such as `7d` (days ago), `12h`, `2w` or `2026-09-01`
+fn parse_date(s: &str, now: &DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return now
+            .timezone()
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .single();
+    }
+    let (last, _) = s.char_indices().last()?;
+    let (number, unit) = s.split_at(last);
+    let number = number.parse::<i64>().ok()?;
+    let ago = match unit {
+        "h" => Duration::hours(number),
+        "d" => Duration::days(number),
+        "w" => Duration::weeks(number),
+        _ => return None,
+    };
+    Some(*now - ago)
+}
+
+fn parse_flag(s: &str) -> Option<Flag> {
+    match s {
+        "unread" | "new" => Some(Flag::Unread),
+        "in-progress" | "inprogress" | "progress" => Some(Flag::InProgress { position: 0 }),
+        "read" | "watched" => Some(Flag::Watched),
+        "skipped" => Some(Flag::Skipped),
+        "starred" | "star" => Some(Flag::Starred),
+        "watch-later" | "watchlater" | "later" => Some(Flag::WatchLater),
+        _ => None,
+    }
+}
+
+/// Predicate of a `field:value` term, `None` when `field` is not one, as in
+/// urls or titles such as `re:zero` typed as bare words
+fn parse_predicate(
+    field: &str,
+    value: &str,
+    now: &DateTime<FixedOffset>,
+) -> Result<Option<Predicate>, QueryError> {
+    let invalid = || QueryError::InvalidValue(field.to_string(), value.to_string());
+    let value_lowercase = value.to_lowercase();
+    let predicate = match field {
+        "channel" => Predicate::Channel(value_lowercase),
+        "title" => Predicate::Title(value_lowercase),
+        "kind" => Predicate::Kind(value_lowercase),
+        "tag" => Predicate::Tag(value_lowercase),
+        "flag" | "is" => parse_flag(&value_lowercase)
+            .map(Predicate::Flag)
+            .ok_or_else(invalid)?,
+        "after" => parse_date(value, now)
+            .map(Predicate::After)
+            .ok_or_else(invalid)?,
+        "before" => parse_date(value, now)
+            .map(Predicate::Before)
+            .ok_or_else(invalid)?,
+        "duration" => {
+            let (ordering, or_equal, length) = if let Some(length) = value.strip_prefix("<=") {
+                (Ordering::Less, true, length)
+            } else if let Some(length) = value.strip_prefix(">=") {
+                (Ordering::Greater, true, length)
+            } else if let Some(length) = value.strip_prefix('<') {
+                (Ordering::Less, false, length)
+            } else if let Some(length) = value.strip_prefix('>') {
+                (Ordering::Greater, false, length)
+            } else {
+                (
+                    Ordering::Equal,
+                    true,
+                    value.strip_prefix('=').unwrap_or(value),
+                )
+            };
+            parse_length(length)
+                .map(|length| Predicate::Duration(ordering, or_equal, length))
+                .ok_or_else(invalid)?
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(predicate))
+}
+
+impl Query {
+    pub fn parse(s: &str) -> Result<Query, QueryError> {
+        Query::parse_at(s, &Local::now().fixed_offset())
+    }
+
+    /// Parses a query, relative dates such as `after:7d` being relative to `now`
+    pub fn parse_at(s: &str, now: &DateTime<FixedOffset>) -> Result<Query, QueryError> {
+        let terms = tokenize(s)?
+            .into_iter()
+            .map(|token| {
+                let (negated, token) = match token.strip_prefix('-') {
+                    Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+                    _ => (false, token),
+                };
+                let predicate = match token.split_once(':') {
+                    Some((field, value)) => parse_predicate(field, value, now)?,
+                    None => None,
+                };
+                let predicate = match predicate {
+                    Some(predicate) => predicate,
+                    None => Predicate::Fuzzy(filter_regex(&token)?),
+                };
+                Ok(Term { negated, predicate })
+            })
+            .collect::<Result<Vec<Term>, QueryError>>()?;
+        Ok(Query { terms })
+    }
+
+    /// Query matching every item
+    pub fn all() -> Query {
+        Query { terms: vec![] }
+    }
+
+    pub fn matches(&self, video: &Item) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.predicate.matches(video) != term.negated)
+    }
+}
+
+impl Predicate {
+    fn matches(&self, video: &Item) -> bool {
+        match self {
+            Predicate::Channel(channel) => std::iter::once(&video.channel)
+                .chain(video.sources.iter())
+                .any(|name| name.to_lowercase().contains(channel)),
+            Predicate::Title(title) => video.title.to_lowercase().contains(title),
+            Predicate::Kind(kind) => format!("{:?}", video.kind).to_lowercase() == *kind,
+            Predicate::Tag(tag) => video.tags.iter().any(|t| t.to_lowercase() == *tag),
+            Predicate::Flag(flag) => flag.same_state(&video.flag),
+            Predicate::After(date) => {
+                DateTime::parse_from_rfc3339(&video.published).is_ok_and(|p| p >= *date)
+            }
+            Predicate::Before(date) => {
+                DateTime::parse_from_rfc3339(&video.published).is_ok_and(|p| p < *date)
+            }
+            Predicate::Duration(ordering, or_equal, length) => video
+                .duration
+                .map(|duration| duration.cmp(length))
+                .is_some_and(|o| o == *ordering || (*or_equal && o == Ordering::Equal)),
+            Predicate::Fuzzy(regex) => regex.is_match(&format!(
+                "{:?}{}{}{}",
+                video.kind,
+                video.channel,
+                video.title,
+                video.sources.join("")
+            )),
+        }
+    }
+}
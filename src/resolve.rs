@@ -0,0 +1,173 @@
+use crate::config::is_channel_id;
+use crate::Logger;
+use futures::stream::{self, StreamExt};
+use regex::Regex;
+use reqwest::Url;
+use std::sync::LazyLock;
+
+static CHANNEL_ID_IN_URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:/channel/|[?&]channel_id=)(UC[0-9A-Za-z_-]{22})").unwrap());
+
+/// Ids of the channel a page is about, most reliable first
+static CHANNEL_ID_IN_PAGE: LazyLock<[Regex; 3]> = LazyLock::new(|| {
+    [
+        Regex::new(r#""externalId":"(UC[0-9A-Za-z_-]{22})""#).unwrap(),
+        Regex::new(r#"<link rel="canonical" href="https://www\.youtube\.com/channel/(UC[0-9A-Za-z_-]{22})""#)
+            .unwrap(),
+        Regex::new(r"https://www\.youtube\.com/channel/(UC[0-9A-Za-z_-]{22})").unwrap(),
+    ]
+});
+
+/// Channel links of a page, such as the saved https://www.youtube.com/feed/channels
+static CHANNEL_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?:https://www\.youtube\.com|")/(?:@([0-9A-Za-z_.%-]+)|channel/(UC[0-9A-Za-z_-]{22}))"#,
+    )
+    .unwrap()
+});
+
+/// Number of channel pages fetched at once
+const PAGE_FETCHES: usize = 8;
+
+/// Channel as configured, in `channel_ids` or `channel_urls`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Channel {
+    Id(String),
+    Url(String),
+}
+
+impl std::fmt::Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Channel::Id(id) => write!(f, "{}", id),
+            Channel::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+impl Channel {
+    /// Channel from a channel id, a YouTube channel or feed url or another
+    /// feed url, `None` for inputs such as handles which need fetching a page
+    pub fn parse(input: &str) -> Option<Channel> {
+        let input = input.trim();
+        if is_channel_id(input) {
+            return Some(Channel::Id(input.to_string()));
+        }
+        // urls without a scheme, as copied from the address bar
+        let youtube = Url::parse(input)
+            .or_else(|_| Url::parse(&format!("https://{}", input)))
+            .is_ok_and(|url| is_youtube(&url));
+        // only on YouTube, feeds such as RSS-Bridge ones may have a channel_id too
+        if youtube {
+            if let Some(caps) = CHANNEL_ID_IN_URL.captures(input) {
+                return Some(Channel::Id(caps[1].to_string()));
+            }
+        }
+        let url = Url::parse(input).ok()?;
+        let youtube_page = is_youtube(&url) && !url.path().starts_with("/feeds/");
+        (!youtube_page).then(|| Channel::Url(input.to_string()))
+    }
+
+    /// Url of the feed, as in the `channel_url` of items
+    pub fn feed_url(&self) -> String {
+        match self {
+            // same as fetch::get_videos
+            Channel::Id(id) => format!("http://www.youtube.com/feeds/videos.xml?channel_id={}", id),
+            Channel::Url(url) => url.clone(),
+        }
+    }
+
+    /// Whether items or OPML outlines with this feed url belong to the channel
+    pub fn has_feed(&self, feed_url: &str) -> bool {
+        match self {
+            Channel::Id(id) => {
+                Url::parse(feed_url).is_ok_and(|url| is_youtube(&url))
+                    && CHANNEL_ID_IN_URL
+                        .captures(feed_url)
+                        .is_some_and(|caps| &caps[1] == id)
+            }
+            Channel::Url(url) => url == feed_url,
+        }
+    }
+}
+
+fn is_youtube(url: &Url) -> bool {
+    url.host_str()
+        .is_some_and(|host| host == "youtube.com" || host.ends_with(".youtube.com"))
+}
+
+/// Id of the channel of a YouTube channel page
+pub fn channel_id_in_page(html: &str) -> Option<String> {
+    CHANNEL_ID_IN_PAGE
+        .iter()
+        .find_map(|regex| regex.captures(html))
+        .map(|caps| caps[1].to_string())
+}
+
+/// Channel of a channel id, url, `@handle` or YouTube channel page url,
+/// fetching the page when the id is not in the input
+pub async fn resolve(input: &str, client: &reqwest::Client) -> Result<Channel, String> {
+    let input = input.trim();
+    if let Some(channel) = Channel::parse(input) {
+        return Ok(channel);
+    }
+    let page_url = match input.strip_prefix('@') {
+        Some(handle) if !handle.is_empty() => format!("https://www.youtube.com/@{}", handle),
+        None if Url::parse(input).is_ok() => input.to_string(),
+        _ => return Err(format!("{} is not a channel id, @handle or url", input)),
+    };
+    let html = client
+        .get(page_url.as_str())
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("failed loading {}: {}", page_url, e))?
+        .text()
+        .await
+        .map_err(|e| format!("failed loading {}: {}", page_url, e))?;
+    channel_id_in_page(&html)
+        .map(Channel::Id)
+        .ok_or_else(|| format!("no channel id found in {}", page_url))
+}
+
+/// Channels linked from a page such as the saved list of subscriptions
+/// (https://www.youtube.com/feed/channels), in order: channel ids, and
+/// `@handles` whose id is not known without fetching their page
+pub fn channels_in_page(html: &str) -> Vec<String> {
+    let mut channels: Vec<String> = vec![];
+    for caps in CHANNEL_LINK.captures_iter(html) {
+        let channel = match (caps.get(1), caps.get(2)) {
+            (Some(handle), _) => format!("@{}", handle.as_str()),
+            (_, Some(id)) => id.as_str().to_string(),
+            _ => continue,
+        };
+        if !channels.contains(&channel) {
+            channels.push(channel);
+        }
+    }
+    channels
+}
+
+/// Resolves the channels of a page, see `channels_in_page`, a few pages
+/// being fetched at once. Channels which failed resolving are reported and
+/// left out.
+pub async fn resolve_page(
+    html: &str,
+    client: &reqwest::Client,
+    debug: &Logger<'_>,
+) -> Vec<Channel> {
+    let resolved = stream::iter(channels_in_page(html))
+        .map(|input| async move { resolve(&input, client).await })
+        .buffered(PAGE_FETCHES)
+        .collect::<Vec<Result<Channel, String>>>()
+        .await;
+    let mut channels: Vec<Channel> = vec![];
+    for channel in resolved {
+        match channel {
+            Ok(channel) if !channels.contains(&channel) => channels.push(channel),
+            Ok(_) => (),
+            Err(e) => debug(&format!("🔴 {}", e)),
+        }
+    }
+    channels
+}
@@ -0,0 +1,110 @@
+use crate::feed::{Flag, Item};
+use crate::Logger;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Rule run on items as they are fetched, such as hiding `#shorts` or
+/// livestream reruns, configured in `rules`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    #[serde(default)]
+    pub field: RuleField,
+    pub regex: String,
+    #[serde(default)]
+    pub action: RuleAction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum RuleField {
+    #[default]
+    Title,
+    Description,
+    Channel,
+    Url,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum RuleAction {
+    /// leaves the item out of the list (`:unhide` shows it back)
+    #[default]
+    Hide,
+    /// tags the item as watched
+    MarkRead,
+    /// adds the name of the rule to the tags of the item
+    Tag,
+}
+
+impl RuleField {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuleField::Title => "title",
+            RuleField::Description => "description",
+            RuleField::Channel => "channel",
+            RuleField::Url => "url",
+        }
+    }
+
+    fn value<'a>(&self, video: &'a Item) -> &'a str {
+        match self {
+            RuleField::Title => &video.title,
+            RuleField::Description => &video.description,
+            RuleField::Channel => &video.channel,
+            RuleField::Url => &video.url,
+        }
+    }
+}
+
+impl RuleAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuleAction::Hide => "hide",
+            RuleAction::MarkRead => "mark read",
+            RuleAction::Tag => "tag",
+        }
+    }
+}
+
+/// Rules with their regexes compiled, rules with an invalid regex left out
+pub struct Rules {
+    rules: Vec<(Rule, Regex)>,
+}
+
+impl Rules {
+    pub fn new(rules: &[Rule], debug: &Logger<'_>) -> Rules {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| match Regex::new(&rule.regex) {
+                Ok(regex) => Some((rule.clone(), regex)),
+                Err(e) => {
+                    debug(&format!("🔴 rule {} ignored: {}", rule.name, e));
+                    None
+                }
+            })
+            .collect();
+        Rules { rules }
+    }
+
+    /// Runs the rules matching a freshly parsed item, recording their names
+    /// in `Item::rules`
+    pub fn apply(&self, video: &mut Item) {
+        for (rule, regex) in self.rules.iter() {
+            if !regex.is_match(rule.field.value(video)) {
+                continue;
+            }
+            if !video.rules.contains(&rule.name) {
+                video.rules.push(rule.name.clone());
+            }
+            match rule.action {
+                RuleAction::Hide => video.hidden = true,
+                RuleAction::MarkRead if video.flag == Flag::Unread => video.flag = Flag::Watched,
+                RuleAction::MarkRead => (),
+                RuleAction::Tag => {
+                    if !video.tags.contains(&rule.name) {
+                        video.tags.push(rule.name.clone());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use crate::cache::Items;
+use crate::config::{replace_home, AppConfig};
+use crate::feed::Item;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader};
+
+/// Weight of a term found in each field of an item
+const TITLE_WEIGHT: f32 = 3.0;
+const CHANNEL_WEIGHT: f32 = 2.0;
+const TEXT_WEIGHT: f32 = 1.0;
+
+/// BM25 parameters
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct IndexedItem {
+    /// weighted number of occurrences of each term
+    terms: HashMap<String, f32>,
+    length: f32,
+    /// see `item_hash`
+    #[serde(default)]
+    hash: u64,
+}
+
+/// Full text index over the title, channel, description and content of
+/// items, kept next to the cache and updated when it is saved
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SearchIndex {
+    items: HashMap<String, IndexedItem>,
+    /// term to ids of the items containing it, built when loading
+    #[serde(skip)]
+    postings: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub id: String,
+    pub score: f32,
+}
+
+/// Lowercased alphanumeric words of a text
+pub fn terms(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_string())
+        .collect()
+}
+
+/// Description and content (rendered as text) of an item
+pub fn item_text(video: &Item) -> String {
+    let content = video
+        .content
+        .as_ref()
+        .map(|content| html2text::from_read(content.as_bytes(), 10000))
+        .unwrap_or_default();
+    // feeds often repeat the description as content
+    if terms(&content) == terms(&video.description) {
+        video.description.clone()
+    } else {
+        format!("{}\n{}", video.description, content)
+    }
+}
+
+/// Hash of the indexed fields of an item, which is indexed again when they
+/// change
+fn item_hash(video: &Item) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (
+        &video.title,
+        &video.channel,
+        &video.description,
+        &video.content,
+    )
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+fn index_item(video: &Item, hash: u64) -> IndexedItem {
+    let mut indexed = IndexedItem {
+        hash,
+        ..Default::default()
+    };
+    for (text, weight) in [
+        (video.title.clone(), TITLE_WEIGHT),
+        (video.channel.clone(), CHANNEL_WEIGHT),
+        (item_text(video), TEXT_WEIGHT),
+    ] {
+        for term in terms(&text) {
+            *indexed.terms.entry(term).or_default() += weight;
+            indexed.length += weight;
+        }
+    }
+    indexed
+}
+
+pub fn index_path(app_config: &AppConfig) -> String {
+    format!("{}.index", replace_home(&app_config.cache_path))
+}
+
+/// Identity of the items and of their indexed fields, so that saving the
+/// cache after a flag change does not rebuild the index
+fn fingerprint(videos: &Items) -> String {
+    let mut ids = videos
+        .videos
+        .iter()
+        .map(|video| (video.id.as_str(), item_hash(video)))
+        .collect::<Vec<(&str, u64)>>();
+    ids.sort_unstable();
+    ids.dedup();
+    let mut hasher = DefaultHasher::new();
+    ids.hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+impl SearchIndex {
+    /// Indexes items, reusing what `previous` already knows about items whose
+    /// indexed fields did not change
+    pub fn build(videos: &[Item], previous: Option<SearchIndex>) -> SearchIndex {
+        let mut previous = previous.map(|index| index.items).unwrap_or_default();
+        let mut index = SearchIndex::default();
+        for video in videos {
+            if index.items.contains_key(&video.id) {
+                continue;
+            }
+            let hash = item_hash(video);
+            let indexed = previous
+                .remove(&video.id)
+                .filter(|indexed| indexed.hash == hash)
+                .unwrap_or_else(|| index_item(video, hash));
+            index.items.insert(video.id.clone(), indexed);
+        }
+        index.build_postings();
+        index
+    }
+
+    fn build_postings(&mut self) {
+        self.postings.clear();
+        for (id, indexed) in self.items.iter() {
+            for term in indexed.terms.keys() {
+                self.postings
+                    .entry(term.clone())
+                    .or_default()
+                    .push(id.clone());
+            }
+        }
+    }
+
+    /// Items containing all the words of the query (the last one may be the
+    /// start of a word), best matches first
+    pub fn search(&self, query: &str) -> Vec<SearchResult> {
+        let words = terms(query);
+        if words.is_empty() || self.items.is_empty() {
+            return vec![];
+        }
+        let count = self.items.len() as f32;
+        let average_length = self.items.values().map(|item| item.length).sum::<f32>() / count;
+        let mut scores: HashMap<&str, f32> = HashMap::new();
+        for (i, word) in words.iter().enumerate() {
+            let prefix = i + 1 == words.len();
+            let matching_terms = self
+                .postings
+                .keys()
+                .filter(|term| *term == word || (prefix && term.starts_with(word.as_str())));
+            let mut word_scores: HashMap<&str, f32> = HashMap::new();
+            for term in matching_terms {
+                let ids = &self.postings[term];
+                let idf = (1.0 + (count - ids.len() as f32 + 0.5) / (ids.len() as f32 + 0.5)).ln();
+                for id in ids {
+                    let item = &self.items[id];
+                    let frequency = item.terms[term];
+                    let score = idf * frequency * (K1 + 1.0)
+                        / (frequency + K1 * (1.0 - B + B * item.length / average_length));
+                    *word_scores.entry(id.as_str()).or_default() += score;
+                }
+            }
+            if i == 0 {
+                scores = word_scores;
+            } else {
+                scores.retain(|id, _| word_scores.contains_key(id));
+                for (id, score) in scores.iter_mut() {
+                    *score += word_scores[id];
+                }
+            }
+        }
+        let mut results = scores
+            .into_iter()
+            .map(|(id, score)| SearchResult {
+                id: id.to_string(),
+                score,
+            })
+            .collect::<Vec<SearchResult>>();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        results
+    }
+}
+
+/// Loads the index saved with the cache
+pub fn load_index(app_config: &AppConfig) -> io::Result<SearchIndex> {
+    let s = fs::read_to_string(index_path(app_config))?;
+    let json = s.split_once('\n').map_or("", |(_, json)| json);
+    let mut index: SearchIndex = serde_json::from_str(json)?;
+    index.build_postings();
+    Ok(index)
+}
+
+/// Indexes items added since the index was last written, the first line of
+/// the file being the fingerprint of the indexed items
+pub fn update_index(app_config: &AppConfig, videos: &Items) -> io::Result<()> {
+    let path = index_path(app_config);
+    let fingerprint = fingerprint(videos);
+    let indexed = fs::File::open(&path).ok().and_then(|file| {
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line).ok()?;
+        Some(line.trim_end().to_string())
+    });
+    if indexed.as_ref() == Some(&fingerprint) {
+        return Ok(());
+    }
+    let index = SearchIndex::build(&videos.videos, load_index(app_config).ok());
+    fs::write(
+        path,
+        format!("{}\n{}", fingerprint, serde_json::to_string(&index)?),
+    )
+}
+
+/// Part of a text around the first word matching a search, about `width`
+/// characters long
+pub fn snippet(text: &str, words: &[String], width: usize) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let lowercase = text.to_lowercase();
+    let position = words
+        .iter()
+        .filter_map(|word| lowercase.find(word.as_str()))
+        .min()?;
+    // byte offsets of the lowercased text may not match the original one
+    let position = lowercase[..position].chars().count();
+    let start = position.saturating_sub(width / 3);
+    let chars = text.chars().collect::<Vec<char>>();
+    let end = (start + width).min(chars.len());
+    let start = end.saturating_sub(width).min(start);
+    Some(format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        chars[start..end].iter().collect::<String>(),
+        if end < chars.len() { "…" } else { "" }
+    ))
+}
+
+/// Surrounds the words of a text starting with one of `words` with `before`
+/// and `after`, such as terminal color escapes
+pub fn highlight(text: &str, words: &[String], before: &str, after: &str) -> String {
+    let words = words.iter().collect::<HashSet<&String>>();
+    let mut result = String::new();
+    let mut word = String::new();
+    let flush = |word: &mut String, result: &mut String| {
+        let lowercase = word.to_lowercase();
+        if !word.is_empty() && words.iter().any(|w| lowercase.starts_with(w.as_str())) {
+            result.push_str(before);
+            result.push_str(word);
+            result.push_str(after);
+        } else {
+            result.push_str(word);
+        }
+        word.clear();
+    };
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut result);
+            result.push(c);
+        }
+    }
+    flush(&mut word, &mut result);
+    result
+}
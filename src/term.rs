@@ -0,0 +1,114 @@
+use crossterm_input::{input, RawScreen};
+use std::io::{self, Write};
+
+pub fn get_lines() -> usize {
+    let size = crossterm::terminal::size();
+    if let Ok((_, h)) = size {
+        (h - 1) as usize
+    } else {
+        20
+    }
+}
+
+pub fn get_cols() -> usize {
+    let size = crossterm::terminal::size();
+    if let Ok((w, _)) = size {
+        w as usize
+    } else {
+        20
+    }
+}
+
+pub fn flush_stdout() {
+    let _ = io::stdout().flush();
+}
+
+pub fn hide_cursor() {
+    print!("\x1b[?25l");
+    flush_stdout();
+}
+
+pub fn smcup() {
+    print!("\x1b[?1049h");
+    flush_stdout();
+}
+
+pub fn rmcup() {
+    print!("\x1b[?1049l");
+    flush_stdout();
+}
+
+pub fn clear() {
+    print!("\x1b[2J");
+    flush_stdout();
+    move_cursor(0, 0);
+}
+
+pub fn show_cursor() {
+    print!("\x1b[?25h");
+    flush_stdout();
+}
+
+pub fn move_cursor(i: usize, j: usize) {
+    print!("\x1b[{};{}f", i + 1, j + 1);
+    flush_stdout();
+}
+
+pub fn move_to_bottom() {
+    print!("\x1b[{};0f", get_lines() + 1);
+    flush_stdout();
+}
+
+pub fn clear_to_end_of_line() {
+    print!("\x1b[K");
+    flush_stdout();
+}
+
+pub fn pause() {
+    let input = input();
+    let _screen = RawScreen::into_raw_mode();
+    let _c = input.read_char();
+}
+
+pub fn print_press_any_key_and_pause() {
+    println!("press any key to continue...");
+    pause();
+}
+
+pub fn count_chars(string: &str) -> usize {
+    string.chars().fold(0, |acc, ch| {
+        acc + (
+            // Check if the character is a Chinese character
+            if ('\u{4E00}'..='\u{9FFF}').contains(&ch) {
+                2
+            } else {
+                1
+            }
+        )
+    })
+}
+
+/// Start of a line taking at most `cols` columns on screen, escape sequences
+/// taking no room and chinese characters two columns
+pub fn fit_width(line: &str, cols: usize) -> String {
+    let mut result = String::new();
+    let mut width = 0;
+    let mut escape = false;
+    for ch in line.chars() {
+        if escape || ch == '\x1b' {
+            // sequences such as "\x1b[34;1m" end with a letter
+            escape = ch == '\x1b' || !ch.is_ascii_alphabetic();
+        } else {
+            width += if ('\u{4E00}'..='\u{9FFF}').contains(&ch) {
+                2
+            } else {
+                1
+            };
+            if width > cols {
+                break;
+            }
+        }
+        result.push(ch);
+    }
+    result
+}
@@ -0,0 +1,1942 @@
+use base64::{engine::general_purpose, Engine as _};
+use blockish::render_image_fitting_terminal;
+use cfonts::{render, Fonts, Options};
+use chrono::NaiveDate;
+use cli_clipboard::{ClipboardContext, ClipboardProvider};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use crossterm_input::KeyEvent::{self, Char, Ctrl, Down, Left, Right, Up};
+use crossterm_input::{input, InputEvent, MouseButton, MouseEvent, RawScreen};
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::StreamExt;
+use regex::Regex;
+use std::cmp::min;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::Write;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+use youtube_subscriptions::cache::{
+    self, channel_item_ids, channel_name, collapse_duplicates, failing_channels, has_id, is_new,
+    is_queued, load_cache, mark_read, move_queued, new_items_summary, published_before,
+    queued_items, remove_channel, set_flag, set_hidden, sorted_channel_statuses, to_show_videos,
+    toggle_queued, Items, SortKey, ViewMode,
+};
+use youtube_subscriptions::config::{
+    edit_channel, replace_home, subscription_manager_relative_path, subscriptions_url, unsubscribe,
+    youtube_base_url, AppConfig,
+};
+use youtube_subscriptions::feed::{format_duration, Flag, Item, ItemKind};
+use youtube_subscriptions::playback::{record_progress, PlaybackProgress};
+use youtube_subscriptions::query::{Query, QueryError};
+use youtube_subscriptions::resolve::resolve;
+use youtube_subscriptions::search::{self, highlight, item_text, load_index, snippet, SearchIndex};
+use youtube_subscriptions::term::{
+    clear, clear_to_end_of_line, count_chars, fit_width, flush_stdout, get_cols, get_lines,
+    hide_cursor, move_cursor, move_to_bottom, pause, rmcup, show_cursor, smcup,
+};
+use youtube_subscriptions::{fetch, notify, playback};
+
+#[derive(Debug)]
+enum CustomError {
+    Io(std::io::Error),
+    Reqwest(reqwest::Error),
+}
+
+impl std::fmt::Display for CustomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CustomError::Io(e) => write!(f, "{}", e),
+            CustomError::Reqwest(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for CustomError {
+    fn from(err: std::io::Error) -> CustomError {
+        CustomError::Io(err)
+    }
+}
+
+impl From<reqwest::Error> for CustomError {
+    fn from(err: reqwest::Error) -> CustomError {
+        CustomError::Reqwest(err)
+    }
+}
+
+fn flag_to_string(app_config: &AppConfig, flag: &Flag) -> String {
+    match app_config.flag_symbols.get(flag.name()) {
+        Some(symbol) => symbol.to_string(),
+        _ => " ".to_string(),
+    }
+}
+
+fn kind_symbol(app_config: &AppConfig, kind: &ItemKind) -> String {
+    match app_config.kind_symbols.get(&format!("{:?}", kind)) {
+        Some(symbol) => symbol.to_string(),
+        _ => " ".to_string(),
+    }
+}
+
+struct YoutubeSubscribtions {
+    modified: SystemTime,
+    col_width: usize,
+    n: usize,
+    start: usize,
+    search: Regex,
+    filter: Query,
+    i: usize,
+    toshow: Vec<Item>,
+    videos: Items,
+    app_config: AppConfig,
+    filter_chars: Vec<char>,
+    playbacks: Vec<UnboundedReceiver<PlaybackProgress>>,
+    /// progress received from the players but not recorded yet
+    finished_playbacks: Vec<PlaybackProgress>,
+    /// ids of the items selected with v, V or A
+    selection: HashSet<String>,
+    /// row where V started a range selection
+    visual_start: Option<usize>,
+    /// index in `views` of the active named view
+    active_view: Option<usize>,
+    filter_text: String,
+    /// configured sort, views may override it
+    default_sort: String,
+    default_sort_key: SortKey,
+    /// configuration file edited by :subscribe and :unsubscribe
+    config_path: Option<String>,
+}
+
+fn print_help() {
+    println!(
+        "\x1b[34;1myoutube-subscriptions\x1b[0m {}",
+        env!("CARGO_PKG_VERSION")
+    );
+    println!("\x1b[36ma tool to view your video subscriptions in a terminal\x1b[0m");
+    println!(
+        "
+  q          quit, waiting for mpv to exit to record its progress (ctrl-c quits now)
+  j,l,down   move down
+  k,up       move up
+  g,H        go to top
+  G,L        go to bottom
+  M          go to middle
+  r,$,left   soft refresh
+  P          previous page
+  N          next page
+  R          full refresh (fetches video list)
+  h,?        prints this help
+  i,right    prints video information
+  S          prints channels status of last full refresh
+  ]          jump to next new item (marked with +)
+  /          search in titles and channels
+  ctrl-f     full text search, ranked results (also :find <words>)
+  f          filter
+  p,enter    plays selected video
+  a          plays selected item audio only
+  o          open selected video in browser
+  v          select unselect a video, then move down
+  V          start end selecting a range of videos
+  A          select all videos matched by the filter
+  esc        clear the selection
+             t,x,*,w,U,y,e,D,X apply to all selected videos
+  t          tag untag a video as watched
+  x          tag untag a video as skipped
+  *          star unstar a video
+  w          tag untag a video to watch later
+  I          tag a video as in progress
+  U          tag a video as unread
+  e          add remove a video to the queue
+  Q          show the queue (reorder, remove, play it all)
+  m          cycle views: all, unread, starred, in progress, queued items
+  z          cycle sort: published, first seen, channel, title, duration,
+             unread first, round robin between channels
+  #          hide show YouTube shorts
+  tab        activate next named view (shift tab for previous)
+  '          pick a named view from a menu
+  F<key>     only show unread (u), in progress (p), watched (t), skipped (x),
+             starred (*), watch later (w) items or all items (a)
+  T          display thumbnail
+  y          copy video url in system clipboard
+  D          download video to video_path with youtube-dl
+  X          hide video (:unhide to show hidden videos back)
+  c          download subscriptions default browser
+  s          enable thumbnail vertical split screen
+  :o <url>             play an url
+  :read channel        tag all videos of the selected video channel as watched
+  :read before <date>  tag all videos published before YYYY-MM-DD as watched
+  :read filtered       tag all videos matched by the filter as watched
+  :unhide              show hidden videos back
+  :rules               show the videos matched by each rule
+  :subscribe <channel> subscribe to a channel id, @handle, channel page or feed url
+  :unsubscribe [<channel>] unsubscribe from a channel (by default the selected video one)
+  :find <words>        search titles, channels, descriptions and contents
+  "
+    )
+}
+
+fn split_cols(string: &str, cols: usize) -> Vec<String> {
+    let mut chars = string.chars();
+    (0..)
+        .map(|_| chars.by_ref().take(cols).collect::<String>())
+        .take_while(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+}
+
+fn info_lines(cols: usize, v: &Item) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    lines.push(format!("\x1b[34;1m{}\x1b[0m", v.title));
+    lines.push("".to_string());
+    lines.push(format!("from \x1b[36m{}\x1b[0m", v.channel));
+    if !v.sources.is_empty() {
+        lines.push(format!("also from \x1b[36m{}\x1b[0m", v.sources.join(", ")));
+    }
+    if let Some(duration) = v.duration {
+        lines.push(format!("duration {}", format_duration(duration)));
+    }
+    if !v.tags.is_empty() {
+        lines.push(format!("tags \x1b[33m{}\x1b[0m", v.tags.join(", ")));
+    }
+    lines.push("".to_string());
+    v.description.split("\n").for_each(|x| {
+        split_cols(x, cols)
+            .iter()
+            .for_each(|y| lines.push(y.to_string()))
+    });
+    if let Some(x) = &v.content {
+        lines.push("".to_string());
+        html2text::from_read(x.as_bytes(), cols)
+            .split("\n")
+            .for_each(|x| lines.push(x.to_string()));
+    }
+    lines
+}
+
+/// What each rule matched when the items were fetched
+fn rules_lines(cols: usize, app_config: &AppConfig, videos: &Items) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    lines.push(format!(
+        "\x1b[34;1m{} rules\x1b[0m (:unhide shows hidden videos back)",
+        app_config.rules.len()
+    ));
+    for rule in app_config.rules.iter() {
+        let matched = videos
+            .videos
+            .iter()
+            .filter(|video| video.rules.contains(&rule.name))
+            .collect::<Vec<&Item>>();
+        lines.push("".to_string());
+        let header = format!(
+            "\x1b[34m{}\x1b[0m: {} when {} matches {}, {} videos",
+            rule.name,
+            rule.action.name(),
+            rule.field.name(),
+            rule.regex,
+            matched.len()
+        );
+        lines.push(header);
+        for video in matched {
+            let line = format!(
+                "  {} \x1b[36m{}\x1b[0m {}",
+                flag_to_string(app_config, &video.flag),
+                video.channel,
+                video.title
+            );
+            lines.push(fit_width(&line, cols));
+        }
+    }
+    lines
+}
+
+fn status_lines(cols: usize, videos: &Items) -> Vec<String> {
+    let statuses = sorted_channel_statuses(videos);
+    let failing = statuses.iter().filter(|(_, s)| !s.is_ok()).count();
+    let mut lines: Vec<String> = vec![];
+    lines.push(format!(
+        "\x1b[34;1m{} channels\x1b[0m, \x1b[31m{} failing\x1b[0m",
+        statuses.len(),
+        failing
+    ));
+    lines.push("".to_string());
+    for (url, status) in statuses {
+        let color = if status.is_ok() { 32 } else { 31 };
+        lines.push(format!(
+            "\x1b[{}m{}\x1b[0m \x1b[34m{}\x1b[0m",
+            color,
+            status.outcome,
+            channel_name(videos, url)
+        ));
+        let details = format!(
+            "  {} retries, {} ms, {} {}",
+            status.retries, status.latency_ms, status.fetched_at, url
+        );
+        split_cols(&details, cols)
+            .iter()
+            .for_each(|y| lines.push(y.to_string()));
+    }
+    lines
+}
+
+fn queue_lines(
+    cols: usize,
+    app_config: &AppConfig,
+    items: &[Item],
+    selected: usize,
+) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    lines.push(format!(
+        "\x1b[34;1mqueue\x1b[0m, {} items (j/k move, J/K reorder, d remove, p play, a audio only, q quit)",
+        items.len()
+    ));
+    lines.push("".to_string());
+    for (i, item) in items.iter().enumerate() {
+        let line = format!(
+            "{}{:>3} {} \x1b[34m{}\x1b[0m {}",
+            if i == selected {
+                "\x1b[1m|\x1b[0m"
+            } else {
+                " "
+            },
+            i + 1,
+            flag_to_string(app_config, &item.flag),
+            item.channel,
+            item.title
+        );
+        lines.push(fit_width(&line, cols));
+    }
+    lines
+}
+
+/// Search results screen, matched words highlighted in the title and in a
+/// snippet of the description or content
+fn search_lines(
+    cols: usize,
+    app_config: &AppConfig,
+    words: &[String],
+    results: &[(Item, Option<String>)],
+    selected: usize,
+) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    lines.push(format!(
+        "\x1b[34;1m{}\x1b[0m results for \x1b[1m{}\x1b[0m (j/k move, p play, a audio only, i info, q quit)",
+        results.len(),
+        words.join(" ")
+    ));
+    lines.push("".to_string());
+    for (i, (item, snippet)) in results.iter().enumerate() {
+        let mut line = fit_width(
+            &format!(
+                "{}{:>3} {} {} {}",
+                if i == selected { "|" } else { " " },
+                i + 1,
+                flag_to_string(app_config, &item.flag),
+                item.channel,
+                item.title
+            ),
+            cols,
+        );
+        line = highlight(&line, words, "\x1b[33;1m", "\x1b[0m");
+        if i == selected {
+            line = format!("\x1b[1m|\x1b[0m{}", &line[1..]);
+        }
+        lines.push(line);
+        if let Some(snippet) = snippet {
+            let snippet = fit_width(snippet, cols.saturating_sub(5));
+            lines.push(format!(
+                "     \x1b[36m{}\x1b[0m",
+                highlight(&snippet, words, "\x1b[33;1m", "\x1b[36m")
+            ));
+        }
+    }
+    lines
+}
+
+fn print_tildeline(x: usize, y: usize) {
+    move_cursor(y, x);
+    print!("\x1b[34;1m~\x1b[0m");
+}
+
+fn print_lines(start_col: usize, lines: &[String], start: usize, rows: usize) {
+    let stop = min(lines.len(), start + rows);
+    for (i, line) in lines[start..stop].iter().enumerate() {
+        move_cursor(i, start_col);
+        print!("{}", line);
+    }
+    if stop >= start {
+        for k in (stop - start)..rows {
+            print_tildeline(start_col, k + start);
+        }
+    }
+}
+
+fn read_key() -> Option<KeyEvent> {
+    let input = input();
+    let _screen = RawScreen::into_raw_mode();
+    match input.read_sync().next() {
+        Some(InputEvent::Keyboard(event)) => Some(event),
+        _ => None,
+    }
+}
+
+fn quit() {
+    show_cursor();
+    rmcup();
+}
+
+async fn write_thumbnail_i(url: &str, video_path: &str) -> Result<String, CustomError> {
+    let path = format!(
+        "{}/{}.{}",
+        video_path,
+        general_purpose::STANDARD_NO_PAD.encode(url),
+        ".jpg"
+    );
+    if fs::metadata(&path).is_ok() {
+        return Ok(path);
+    }
+    let resp = reqwest::get(url).await?;
+    let mut out = File::create(&path)?;
+    let bytes = resp.bytes().await?;
+    out.write_all(&bytes[..])?;
+    Ok(path)
+}
+
+async fn render_thumbnail(url: String, p: String, pos: Option<(u32, u32)>) {
+    if let Ok(path) = write_thumbnail_i(&url, &p).await {
+        blockish::render_image(path.as_str(), (get_cols() * 8 / 2) as u32, pos)
+    }
+}
+
+impl YoutubeSubscribtions {
+    fn print_thumbnail(&self) {
+        if self.i < self.toshow.len() {
+            move_cursor(0, 0);
+            let i = self.i;
+            let channel = &self.toshow[i].channel;
+            let output = render(Options {
+                text: String::from(channel),
+                font: Fonts::FontChrome,
+                ..Options::default()
+            });
+            for line in output.vec {
+                println!("{}", line);
+            }
+            let title = &self.toshow[i].title;
+            println!("{}", title);
+            let url = &self.toshow[i].thumbnail;
+            let p = &self.app_config.video_path;
+            let pos: (u32, u32) = (0, 10);
+            tokio::spawn(render_thumbnail(url.clone(), p.clone(), Some(pos)));
+        }
+    }
+
+    fn print_videos(&mut self) {
+        let (cols, start_col) = self.get_cols_and_start_col();
+        let rows = get_lines();
+        let channel_max_size = cols / 3;
+        let max = self.toshow.iter().fold(0, |acc, x| {
+            std::cmp::max(
+                std::cmp::min(count_chars(&x.channel), channel_max_size),
+                acc,
+            )
+        });
+        self.col_width = max + 11;
+        for (i, video) in self.toshow.iter().enumerate() {
+            let published = video.published.split('T').collect::<Vec<&str>>();
+            let whitespaces =
+                " ".repeat(max - std::cmp::min(count_chars(&video.channel), channel_max_size));
+            let channel_short = video
+                .channel
+                .chars()
+                .take(channel_max_size)
+                .collect::<String>();
+            let published_short = if !published.is_empty() && published[0].len() >= 10 {
+                published[0][5..10].to_string()
+            } else {
+                "?? ??".to_string()
+            };
+            let new_marker = if self.is_selected(i, video) {
+                "\x1b[32;1m●\x1b[0m"
+            } else if is_new(&self.videos, video) {
+                "\x1b[33;1m+\x1b[0m"
+            } else if is_queued(&self.videos, video) {
+                "\x1b[35mQ\x1b[0m"
+            } else {
+                " "
+            };
+            let progress = match (&video.flag, video.duration) {
+                (Flag::InProgress { position }, Some(duration)) if duration > 0 => {
+                    format!("\x1b[33m{}%\x1b[0m ", position * 100 / duration)
+                }
+                _ => "".to_string(),
+            };
+            let s = format!(
+                "{}{} {} \x1b[36m{}\x1b[0m \x1b[34m{}\x1b[0m{}  {}{}",
+                new_marker,
+                flag_to_string(&self.app_config, &video.flag),
+                kind_symbol(&self.app_config, &video.kind),
+                published_short,
+                channel_short,
+                whitespaces,
+                progress,
+                video.title
+            );
+            move_cursor(i, start_col);
+            print!("{}", fit_width(&s, cols.saturating_sub(4)));
+        }
+        if self.toshow.len() < rows {
+            for k in 0..(rows - self.toshow.len()) {
+                print_tildeline(start_col, k);
+            }
+        }
+        if self.app_config.split_thumbnail {
+            self.print_thumbnail();
+        }
+        if let Some(status) = self.status_line() {
+            move_to_bottom();
+            clear_to_end_of_line();
+            print!("{}", status.chars().take(cols).collect::<String>());
+            flush_stdout();
+        }
+    }
+
+    /// Active view, view mode and filter, when any
+    fn status_line(&self) -> Option<String> {
+        let mut parts = vec![];
+        if let Some(view) = self.active_view.and_then(|i| self.app_config.views.get(i)) {
+            parts.push(format!("[{}]", view.name));
+        }
+        if self.videos.view_mode != ViewMode::All {
+            parts.push(format!("{} items", self.videos.view_mode.name()));
+        }
+        if !self.filter_text.is_empty() {
+            parts.push(format!("filter {}", self.filter_text));
+        }
+        if self.app_config.hide_shorts {
+            parts.push("no shorts".to_string());
+        }
+        if self.app_config.sort_key != SortKey::Published {
+            parts.push(format!("sorted by {}", self.app_config.sort_key.name()));
+        }
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+
+    /// Applies the filter, sort and view mode of a named view, or goes back
+    /// to the configured sort without filter
+    fn set_view(&mut self, view: Option<usize>) {
+        let named_view = view.and_then(|i| self.app_config.views.get(i)).cloned();
+        self.active_view = named_view.as_ref().and(view);
+        let (query, sort, sort_key, view_mode) = match named_view {
+            Some(named_view) => (
+                named_view.query,
+                named_view.sort.unwrap_or(self.default_sort.clone()),
+                named_view.sort_key.unwrap_or(self.default_sort_key),
+                named_view.view_mode,
+            ),
+            None => (
+                "".to_string(),
+                self.default_sort.clone(),
+                self.default_sort_key,
+                ViewMode::All,
+            ),
+        };
+        self.app_config.sort = sort;
+        self.app_config.sort_key = sort_key;
+        self.videos.view_mode = view_mode;
+        self.save_videos(&self.app_config, &self.videos);
+        self.filter_chars = query.chars().collect();
+        if let Err(e) = self.set_filter(&query) {
+            self.debug(&format!("invalid query in view: {}", e));
+        }
+    }
+
+    /// Activates the next (or previous) named view, no view being part of the cycle
+    fn cycle_view(&mut self, forward: bool) {
+        let count = self.app_config.views.len();
+        if count == 0 {
+            self.debug("no view configured (see views in the configuration)");
+            return;
+        }
+        let view = match (self.active_view, forward) {
+            (None, true) => Some(0),
+            (None, false) => Some(count - 1),
+            (Some(i), true) if i + 1 < count => Some(i + 1),
+            (Some(i), false) if i > 0 => Some(i - 1),
+            _ => None,
+        };
+        self.set_view(view);
+    }
+
+    /// Menu listing named views, picked with j/k and enter or their number
+    fn views_menu(&mut self) {
+        let mut i = self.active_view.map_or(0, |i| i + 1);
+        loop {
+            let names = std::iter::once("no view".to_string())
+                .chain(self.app_config.views.iter().map(|view| view.name.clone()))
+                .collect::<Vec<String>>();
+            let (cols, start_col) = self.get_cols_and_start_col();
+            clear();
+            let mut lines = vec![
+                "\x1b[34;1mviews\x1b[0m (j/k move, enter or number pick, q quit)".to_string(),
+                "".to_string(),
+            ];
+            for (j, name) in names.iter().enumerate() {
+                let line = format!(
+                    "{}{:>3} {}",
+                    if i == j { "\x1b[1m|\x1b[0m" } else { " " },
+                    j,
+                    name
+                );
+                lines.push(fit_width(&line, cols - 1));
+            }
+            print_lines(start_col + 1, &lines, 0, get_lines());
+            flush_stdout();
+            match read_key() {
+                Some(Char('j')) | Some(Down) if i + 1 < names.len() => i += 1,
+                Some(Char('k')) | Some(Up) if i > 0 => i -= 1,
+                Some(Char(c)) if c.is_ascii_digit() => {
+                    let j = c.to_digit(10).unwrap_or(0) as usize;
+                    if j < names.len() {
+                        i = j;
+                        break;
+                    }
+                }
+                Some(KeyEvent::Enter) => break,
+                Some(Char('q')) | Some(Left) | Some(KeyEvent::Esc) => {
+                    self.clear_and_print_videos();
+                    return;
+                }
+                _ => (),
+            }
+        }
+        self.set_view(i.checked_sub(1));
+    }
+
+    fn clear_and_print_videos(&mut self) {
+        clear();
+        self.print_videos()
+    }
+
+    fn download_subscriptions(&self) {
+        let _res = webbrowser::open(subscriptions_url());
+        self.debug(&format!(
+            "please save file to ~/{}",
+            subscription_manager_relative_path()
+        ));
+    }
+
+    async fn load(&self, reload: bool) -> Option<Items> {
+        let path = replace_home(&self.app_config.cache_path);
+        if reload || fs::metadata(&path).is_err() {
+            fetch::refresh(&self.app_config, &self.videos, &|s| self.debug(s)).await
+        } else {
+            match load_cache(&self.app_config) {
+                Ok(res) => Some(res),
+                Err(e) => {
+                    self.debug(&format!("failed reading {} {}", path, e));
+                    None
+                }
+            }
+        }
+    }
+
+    fn save_videos(&self, app_config: &AppConfig, videos: &Items) {
+        if let Err(e) = cache::save_videos(app_config, videos) {
+            self.debug(&format!(
+                "failed writing {} {}",
+                replace_home(&app_config.cache_path),
+                e
+            ));
+        }
+    }
+
+    fn debug(&self, s: &str) {
+        move_to_bottom();
+        clear_to_end_of_line();
+        move_to_bottom();
+        print!("{}", s);
+        flush_stdout();
+    }
+
+    fn move_page(&mut self, direction: i8) {
+        if self.visual_start.is_some() {
+            // a range does not span pages, keep what was selected so far
+            self.toggle_visual();
+        }
+        self.n = get_lines();
+        if direction == 1 {
+            if self.start + 2 * self.n < self.videos.videos.len() {
+                self.start += self.n;
+            }
+        } else if direction == 0 {
+            self.start = 0;
+        } else if direction == -1 {
+            if self.n > self.start {
+                self.start = 0;
+            } else {
+                self.start -= self.n;
+            }
+        }
+        self.toshow = to_show_videos(
+            &self.app_config,
+            &mut self.videos,
+            self.start,
+            self.start + self.n,
+            &self.filter,
+        );
+        self.i = 0;
+        self.clear_and_print_videos()
+    }
+
+    fn next_page(&mut self) {
+        self.move_page(-1);
+    }
+
+    fn previous_page(&mut self) {
+        self.move_page(1);
+    }
+
+    fn cache_modified(&self) -> bool {
+        let path = replace_home(&self.app_config.cache_path);
+        match fs::metadata(&path) {
+            Ok(metadata) => {
+                let modified = metadata.modified().unwrap();
+                self.modified < modified
+            }
+            Err(_) => false,
+        }
+    }
+
+    async fn soft_reload(&mut self) {
+        if self.cache_modified() {
+            self.load_videos_from_cache().await;
+        }
+        self.move_page(0);
+    }
+
+    async fn hard_reload(&mut self) {
+        let now = Instant::now();
+        self.debug("updating video list...");
+        match self.load(true).await {
+            Some(videos) => self.videos = videos,
+            None => {
+                let msg = "🔴 could not load videos".to_string();
+                self.debug(&msg);
+                notify(&msg);
+            }
+        }
+        self.debug("");
+        let failing = failing_channels(&self.videos);
+        let msg = if failing > 0 {
+            format!(
+                "✅ reload took {} ms, {}, {} channels failed (press S)",
+                now.elapsed().as_millis(),
+                new_items_summary(&self.app_config, &self.videos),
+                failing
+            )
+        } else {
+            format!(
+                "✅ reload took {} ms, {}",
+                now.elapsed().as_millis(),
+                new_items_summary(&self.app_config, &self.videos)
+            )
+        };
+        self.debug(&msg);
+        notify(&msg);
+    }
+
+    fn first_page(&mut self) {
+        self.n = get_lines();
+        self.toshow = to_show_videos(
+            &self.app_config,
+            &mut self.videos,
+            self.start,
+            self.n,
+            &self.filter,
+        );
+    }
+
+    fn play_current(&mut self, no_video: bool) {
+        if self.i < self.toshow.len() {
+            match self.play(&self.toshow[self.i], &self.app_config, no_video) {
+                Some(receiver) => self.playbacks.push(receiver),
+                None => self.flag_played(),
+            }
+            self.clear_and_print_videos();
+        }
+    }
+
+    async fn write_thumbnail(&self, i: usize) -> Result<String, CustomError> {
+        let url = &self.toshow[i].thumbnail;
+        write_thumbnail_i(url, &self.app_config.video_path).await
+    }
+
+    async fn display_current_thumbnail(&mut self) -> Result<(), CustomError> {
+        if self.i < self.toshow.len() {
+            let path = self.write_thumbnail(self.i).await?;
+            clear();
+            render_image_fitting_terminal(&path);
+            self.wait_key_press_and_clear_and_print_videos();
+        }
+        Ok(())
+    }
+
+    async fn current_thumbnail_to_thumbnail_jpg(&mut self) -> Result<(), CustomError> {
+        if let Some(thumbnail_path) = self.app_config.auto_thumbnail_path.clone() {
+            if self.i < self.toshow.len() {
+                let i = self.i;
+                let path = self.write_thumbnail(i).await?;
+                // copy path to ytsthumbnail.jpg
+                let channel = &self.toshow[i].channel;
+                let title = &self.toshow[i].title;
+                // write channel and title to a $thumbnail_path.jpg.txt
+                let mut file = File::create(format!("{}.txt", thumbnail_path))?;
+                file.write_all(channel.as_bytes())?;
+                file.write_all(b"\n")?;
+                file.write_all(title.as_bytes())?;
+                file.flush()?;
+                fs::copy(&path, &thumbnail_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn open_current(&mut self, channel: bool) {
+        if self.i < self.toshow.len() {
+            let item = &self.toshow[self.i];
+            let base_url = if channel {
+                &item.channel_url
+            } else {
+                &item.url
+            };
+            let url = base_url.replace(&youtube_base_url(), &self.app_config.youtube_instance);
+            self.debug(&format!("opening {}", &url));
+            let _res = webbrowser::open(&url);
+            self.flag_played();
+            self.clear_and_print_videos();
+        }
+    }
+
+    fn find_next(&mut self) -> usize {
+        for (i, video) in self.toshow.iter().enumerate() {
+            if i > self.i
+                && (self.search.is_match(&video.title) || self.search.is_match(&video.channel))
+            {
+                return i;
+            }
+        }
+        self.i
+    }
+
+    fn realtime_input_with_prefix(&mut self, start_symbol: &str) -> Option<String> {
+        move_to_bottom();
+        clear_to_end_of_line();
+        print!("{}", start_symbol);
+        for c in self.filter_chars.iter() {
+            print!("{}", c);
+        }
+        flush_stdout();
+        let _ = enable_raw_mode();
+        let input = input();
+        let mut reader = input.read_sync();
+        match reader.next() {
+            Some(InputEvent::Keyboard(KeyEvent::Backspace)) => {
+                self.filter_chars.pop();
+            }
+            Some(InputEvent::Keyboard(KeyEvent::Enter)) => {
+                let _ = disable_raw_mode();
+                return None;
+            }
+            Some(InputEvent::Keyboard(KeyEvent::Char(c))) => {
+                print!("{}", c);
+                self.filter_chars.push(c)
+            }
+            _ => {}
+        }
+        let _ = disable_raw_mode();
+        Some(self.filter_chars.iter().collect::<String>())
+    }
+
+    fn input_with_prefix(&mut self, start_symbol: &str) -> String {
+        move_to_bottom();
+        clear_to_end_of_line();
+        print!("{}", start_symbol);
+        flush_stdout();
+        let input = input();
+        input.read_line().unwrap_or("".to_string())
+    }
+
+    fn search_next(&mut self) {
+        self.clear_selector(self.i, self.col_width);
+        self.i = self.find_next();
+    }
+
+    fn jump_to_next_new(&mut self) {
+        let n = get_lines();
+        let mut start = self.start;
+        let mut after = Some(self.i);
+        while start < self.videos.videos.len() {
+            let page = to_show_videos(
+                &self.app_config,
+                &mut self.videos,
+                start,
+                start + n,
+                &self.filter,
+            );
+            let found = page
+                .iter()
+                .enumerate()
+                .position(|(i, video)| after.is_none_or(|j| i > j) && is_new(&self.videos, video));
+            if let Some(i) = found {
+                self.n = n;
+                self.start = start;
+                self.toshow = page;
+                self.i = i;
+                self.clear_and_print_videos();
+                return;
+            }
+            start += n;
+            after = None;
+        }
+        self.debug("no more new item");
+    }
+
+    fn search(&mut self) {
+        let s = self.input_with_prefix("/");
+        match Regex::new(&format!(".*(?i){}.*", s)) {
+            Ok(regex) => {
+                self.search = regex;
+                self.i = self.find_next();
+            }
+            Err(_) => self.debug("failing creating regex"),
+        }
+        self.clear_and_print_videos()
+    }
+
+    fn find(&mut self) {
+        let s = self.input_with_prefix("find: ");
+        hide_cursor();
+        self.search_results(&s);
+    }
+
+    /// Ranked full text search over titles, channels, descriptions and
+    /// contents, see `search::SearchIndex`
+    fn search_results(&mut self, query: &str) {
+        let words = search::terms(query);
+        if words.is_empty() {
+            self.clear_and_print_videos();
+            return;
+        }
+        // a failing index only costs indexing everything for this search
+        if let Err(e) = search::update_index(&self.app_config, &self.videos) {
+            self.debug(&format!(
+                "failed writing {} {}",
+                search::index_path(&self.app_config),
+                e
+            ));
+        }
+        let index = load_index(&self.app_config)
+            .unwrap_or_else(|_| SearchIndex::build(&self.videos.videos, None));
+        // whatever the view, hidden items aside
+        let mut items = self.videos.videos.clone();
+        collapse_duplicates(&mut items);
+        items.retain(|item| !item.hidden);
+        // copies of an item are all indexed, they lead to the collapsed item
+        let by_id = items
+            .iter()
+            .enumerate()
+            .flat_map(|(i, item)| {
+                std::iter::once(&item.id)
+                    .chain(item.aliases.iter())
+                    .map(move |id| (id.as_str(), i))
+            })
+            .collect::<HashMap<&str, usize>>();
+        let mut seen = HashSet::new();
+        let mut results = index
+            .search(query)
+            .into_iter()
+            .filter_map(|result| by_id.get(result.id.as_str()))
+            .filter(|i| seen.insert(**i))
+            .map(|i| {
+                (
+                    items[*i].clone(),
+                    snippet(&item_text(&items[*i]), &words, 200),
+                )
+            })
+            .collect::<Vec<(Item, Option<String>)>>();
+        if results.is_empty() {
+            self.clear_and_print_videos();
+            self.debug(&format!("no result for {}", query.trim()));
+            return;
+        }
+        let mut i = 0;
+        loop {
+            let (cols, start_col) = self.get_cols_and_start_col();
+            let rows = get_lines();
+            clear();
+            let lines = search_lines(cols - 1, &self.app_config, &words, &results, i);
+            let selected_line = 2 + results[..i]
+                .iter()
+                .map(|(_, s)| 1 + s.is_some() as usize)
+                .sum::<usize>();
+            let first = (selected_line + 2).saturating_sub(rows.saturating_sub(1));
+            print_lines(start_col + 1, &lines, first, rows);
+            flush_stdout();
+            match read_key() {
+                Some(Char('q')) | Some(Left) => break,
+                Some(Char('j')) | Some(Down) if i + 1 < results.len() => i += 1,
+                Some(Char('k')) | Some(Up) if i > 0 => i -= 1,
+                Some(Char('i')) | Some(Right) => self.print_info(&results[i].0.clone()),
+                Some(Char('p')) | Some(KeyEvent::Enter) => {
+                    self.play_result(&mut results[i].0, false)
+                }
+                Some(Char('a')) => self.play_result(&mut results[i].0, true),
+                _ => (),
+            }
+        }
+        self.clear_and_print_videos()
+    }
+
+    fn play_result(&mut self, item: &mut Item, no_video: bool) {
+        match self.play(item, &self.app_config, no_video) {
+            Some(receiver) => self.playbacks.push(receiver),
+            None if item.flag != Flag::Starred => {
+                self.flag_items(std::slice::from_ref(item), &Flag::Watched);
+                item.flag = Flag::Watched;
+            }
+            None => (),
+        }
+    }
+
+    /// Applies a filter query, see `query::Query`. While typing the query may
+    /// be incomplete, the last valid one is then kept.
+    fn set_filter(&mut self, s: &str) -> Result<(), QueryError> {
+        let query = Query::parse(s)?;
+        self.filter = query;
+        self.filter_text = s.trim().to_string();
+        self.move_page(0);
+        self.clear_and_print_videos();
+        Ok(())
+    }
+
+    fn filter(&mut self) {
+        self.filter_chars = vec![];
+        let mut result = self.set_filter("");
+        while let Some(s) = self.realtime_input_with_prefix("|") {
+            result = self.set_filter(&s);
+        }
+        if let Err(e) = result {
+            self.debug(&format!("invalid filter: {}", e));
+        }
+    }
+
+    fn play(
+        &self,
+        v: &Item,
+        app_config: &AppConfig,
+        no_video: bool,
+    ) -> Option<UnboundedReceiver<PlaybackProgress>> {
+        playback::play(v, app_config, no_video, &|s| self.debug(s))
+    }
+
+    /// Adds the selected items to the queue, or removes them if they all are queued
+    fn toggle_queued(&mut self) {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return;
+        }
+        let queue = !targets.iter().all(|item| is_queued(&self.videos, item));
+        for item in targets.iter() {
+            if is_queued(&self.videos, item) != queue {
+                toggle_queued(&mut self.videos, item);
+            }
+        }
+        self.save_videos(&self.app_config, &self.videos);
+        self.clear_selection();
+        self.clear_and_print_videos();
+        self.debug(&if queue {
+            format!("added {} items to queue (press Q to see it)", targets.len())
+        } else {
+            format!("removed {} items from queue", targets.len())
+        });
+    }
+
+    /// Plays queued items from the `start`th one
+    fn play_queue(&mut self, start: usize, no_video: bool) {
+        let items = queued_items(&self.videos);
+        if start >= items.len() {
+            return;
+        }
+        let receiver = playback::play_queue(&items[start..], &self.app_config, no_video, &|s| {
+            self.debug(s)
+        });
+        match receiver {
+            Some(receiver) => self.playbacks.push(receiver),
+            None => {
+                // other players do not report a position, they were played to the end
+                for item in items[start..].iter() {
+                    if item.flag != Flag::Starred {
+                        set_flag(&mut self.videos.videos, item, &Flag::Watched);
+                    }
+                }
+                self.videos
+                    .queue
+                    .retain(|id| !items[start..].iter().any(|item| item.id == *id));
+                self.save_videos(&self.app_config, &self.videos);
+            }
+        }
+    }
+
+    /// Queue screen: move with j/k, reorder with J/K, remove with d, play
+    /// from the selected item with p (a for audio only)
+    fn queue(&mut self) {
+        let mut i = 0;
+        loop {
+            let items = queued_items(&self.videos);
+            let (cols, start_col) = self.get_cols_and_start_col();
+            let rows = get_lines();
+            clear();
+            let lines = queue_lines(cols - 1, &self.app_config, &items, i);
+            let first = (i + 1).saturating_sub(rows.saturating_sub(2));
+            print_lines(start_col + 1, &lines, first, rows);
+            flush_stdout();
+            match read_key() {
+                Some(Char('q')) | Some(Left) | Some(Char('Q')) => break,
+                Some(Char('j')) | Some(Down) if i + 1 < items.len() => i += 1,
+                Some(Char('k')) | Some(Up) if i > 0 => i -= 1,
+                Some(Char('J')) => i = move_queued(&mut self.videos, i, 1),
+                Some(Char('K')) => i = move_queued(&mut self.videos, i, -1),
+                Some(Char('d')) | Some(Char('e')) if i < items.len() => {
+                    toggle_queued(&mut self.videos, &items[i]);
+                    i = i.min(items.len().saturating_sub(2));
+                }
+                Some(Char('p')) | Some(KeyEvent::Enter) => {
+                    self.play_queue(i, false);
+                    break;
+                }
+                Some(Char('a')) => {
+                    self.play_queue(i, true);
+                    break;
+                }
+                _ => (),
+            }
+            self.save_videos(&self.app_config, &self.videos);
+        }
+        self.clear_and_print_videos()
+    }
+
+    /// Takes the progress reported by the players since the last call, dropping
+    /// the receivers of players which are done with their queue
+    fn poll_playbacks(&mut self) -> bool {
+        let mut changed = false;
+        let finished = &mut self.finished_playbacks;
+        self.playbacks.retain_mut(|receiver| loop {
+            match receiver.try_next() {
+                Ok(Some(progress)) => {
+                    finished.push(progress);
+                    changed = true;
+                }
+                // the player and the rest of its queue are done
+                Ok(None) => {
+                    changed = true;
+                    break false;
+                }
+                Err(_) => break true,
+            }
+        });
+        changed
+    }
+
+    /// Records the position of items whose mpv exited, marking played queue
+    /// items as watched
+    fn check_playbacks(&mut self) {
+        self.poll_playbacks();
+        if self.finished_playbacks.is_empty() {
+            return;
+        }
+        for progress in std::mem::take(&mut self.finished_playbacks).iter() {
+            record_progress(&mut self.videos, progress, &self.app_config);
+        }
+        self.save_videos(&self.app_config, &self.videos);
+        self.update_toshow();
+        self.clear_and_print_videos();
+    }
+
+    /// Waits for the players still running so that quitting does not lose
+    /// where they stopped, ctrl-c quits right away
+    async fn drain_playbacks(&mut self) {
+        if !self.playbacks.is_empty() {
+            self.debug(
+                "waiting for the player to exit to record its progress (ctrl-c to quit now)",
+            );
+        }
+        for mut receiver in std::mem::take(&mut self.playbacks) {
+            while let Some(progress) = receiver.next().await {
+                self.finished_playbacks.push(progress);
+            }
+        }
+        self.check_playbacks();
+    }
+
+    fn play_url(&self, url: &str, kind: &ItemKind, app_config: &AppConfig, no_video: bool) {
+        playback::play_url(url, kind, app_config, no_video, &|s| self.debug(s));
+    }
+
+    async fn command(&mut self) {
+        let s = self.input_with_prefix(":");
+        let s = s.split_whitespace().collect::<Vec<&str>>();
+        hide_cursor();
+        clear();
+        let message = match s.as_slice() {
+            ["o", url] => {
+                self.play_url(url, &ItemKind::Video, &self.app_config, false);
+                None
+            }
+            ["find", ..] if s.len() > 1 => {
+                self.search_results(&s[1..].join(" "));
+                None
+            }
+            ["subscribe", input] => Some(self.subscribe(input).await),
+            ["unsubscribe"] => match self.toshow.get(self.i) {
+                Some(video) => {
+                    let channel_url = video.channel_url.clone();
+                    Some(self.unsubscribe(&channel_url).await)
+                }
+                None => None,
+            },
+            ["unsubscribe", input] => Some(self.unsubscribe(input).await),
+            ["rules"] => {
+                self.rules();
+                None
+            }
+            ["unhide"] => {
+                self.unhide_all();
+                None
+            }
+            ["read", "channel"] => match self.toshow.get(self.i) {
+                Some(video) => {
+                    // displayed channel names may be renamed in the configuration
+                    let ids = channel_item_ids(&self.videos.videos, &video.channel_url);
+                    Some(self.mark_read(|video| has_id(video, &ids)))
+                }
+                None => None,
+            },
+            ["read", "before", date] => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                Ok(date) => Some(self.mark_read(|video| published_before(video, &date))),
+                Err(_) => Some(format!("{} is not a YYYY-MM-DD date", date)),
+            },
+            ["read", "filtered"] => {
+                let len = self.videos.videos.len();
+                let ids = to_show_videos(&self.app_config, &mut self.videos, 0, len, &self.filter)
+                    .into_iter()
+                    .map(|video| video.id)
+                    .collect::<HashSet<String>>();
+                Some(self.mark_read(|video| ids.contains(&video.id)))
+            }
+            [] => None,
+            _ => Some("unknown command (press h for help)".to_string()),
+        };
+        self.clear_and_print_videos();
+        if let Some(message) = message {
+            self.debug(&message);
+        }
+    }
+
+    /// Adds a channel to the configuration and fetches it
+    async fn subscribe(&mut self, input: &str) -> String {
+        let Some(config_path) = self.config_path.clone() else {
+            return "could not find configuration path".to_string();
+        };
+        self.debug(&format!("resolving {}...", input));
+        let channel = match resolve(input, &fetch::client()).await {
+            Ok(channel) => channel,
+            Err(e) => return e,
+        };
+        match edit_channel(&config_path, &channel, true) {
+            Ok(true) => (),
+            Ok(false) => return format!("already subscribed to {}", channel),
+            Err(e) => return format!("failed writing {}: {}", config_path, e),
+        }
+        self.app_config.add_channel(&channel);
+        self.debug(&format!("fetching {}...", channel));
+        let channel_url = channel.feed_url();
+        self.videos = fetch::refresh_channel(&self.app_config, &self.videos, &channel_url, &|s| {
+            self.debug(s)
+        })
+        .await;
+        self.update_toshow();
+        let count = self
+            .videos
+            .videos
+            .iter()
+            .filter(|video| video.channel_url == channel_url)
+            .count();
+        format!("subscribed to {}, {} videos", channel, count)
+    }
+
+    /// Removes a channel from the configuration or the subscription_manager
+    /// file, and its videos from the list
+    async fn unsubscribe(&mut self, input: &str) -> String {
+        let Some(config_path) = self.config_path.clone() else {
+            return "could not find configuration path".to_string();
+        };
+        let channel = match resolve(input, &fetch::client()).await {
+            Ok(channel) => channel,
+            Err(e) => return e,
+        };
+        match unsubscribe(&config_path, &channel) {
+            Ok(true) => (),
+            Ok(false) => return format!("not subscribed to {}", channel),
+            Err(e) => return e,
+        }
+        self.app_config.remove_channel(&channel);
+        remove_channel(&mut self.videos, &channel);
+        self.save_videos(&self.app_config, &self.videos);
+        self.update_toshow();
+        format!("unsubscribed from {}", channel)
+    }
+
+    /// Tags matching items as watched and saves once, see `cache::mark_read`
+    fn mark_read<F: Fn(&Item) -> bool>(&mut self, matches: F) -> String {
+        let count = mark_read(&mut self.videos.videos, matches);
+        self.save_videos(&self.app_config, &self.videos);
+        self.update_toshow();
+        format!("tagged {} items as watched", count)
+    }
+
+    fn yank_video_uri(&mut self) {
+        let targets = self.targets();
+        let urls = targets
+            .iter()
+            .map(|video| video.url.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let message = match targets.len() {
+            1 => urls.clone(),
+            n => format!("{} urls", n),
+        };
+        match ClipboardContext::new() {
+            Ok(mut ctx) => match ctx.set_contents(urls) {
+                Ok(_) => self.debug(&format!("yanked {}", message)),
+                Err(e) => self.debug(&format!("failed yanking {}: {}", message, e)),
+            },
+            Err(e) => self.debug(&format!("error: {:?}", e)),
+        }
+        self.clear_selection();
+    }
+
+    fn wait_key_press_and_clear_and_print_videos(&mut self) {
+        pause();
+        self.clear_and_print_videos()
+    }
+
+    /* for this to work, each line should not be greater than the number of cols and there should not
+     * be any line feed */
+    fn less<F>(&mut self, generate_lines: F)
+    where
+        F: Fn(usize) -> Vec<String>,
+    {
+        let rows = get_lines();
+        let delta = rows / 2;
+        let mut i = 0;
+        loop {
+            let (cols, start_col) = self.get_cols_and_start_col();
+            clear();
+            let lines = generate_lines(cols - 1);
+            print_lines(start_col + 1, &lines, i, rows);
+            if self.app_config.split_thumbnail {
+                self.print_thumbnail();
+            }
+            let input = input();
+            let result;
+            {
+                let _screen = RawScreen::into_raw_mode();
+                let mut stdin = input.read_sync();
+                result = stdin.next();
+            }
+            match result {
+                None => (),
+                Some(InputEvent::Keyboard(event)) => match event {
+                    Char('s') => {
+                        self.app_config.split_thumbnail = !self.app_config.split_thumbnail;
+                    }
+                    Char('q') | Left => {
+                        break;
+                    }
+                    Char('g') => {
+                        i = 0;
+                    }
+                    Char('G') => {
+                        i = lines.len() - 1;
+                    }
+                    Char('k') | Up if i > 0 => {
+                        i -= 1;
+                    }
+                    Char('j') | Down if i < lines.len() => {
+                        i += 1;
+                    }
+                    Ctrl('u') => {
+                        if i > delta {
+                            i -= delta
+                        } else {
+                            i = 0
+                        };
+                    }
+                    Ctrl('d') => {
+                        if i + delta < lines.len() {
+                            i += delta
+                        } else {
+                            i = lines.len()
+                        };
+                    }
+                    _ => {}
+                },
+                _ => (),
+            }
+        }
+    }
+
+    fn print_info(&mut self, v: &Item) {
+        self.less(|c| info_lines(c, v));
+    }
+
+    fn rules(&mut self) {
+        let videos = std::mem::take(&mut self.videos);
+        let app_config = self.app_config.clone();
+        self.less(|c| rules_lines(c, &app_config, &videos));
+        self.videos = videos;
+    }
+
+    fn channel_status(&mut self) {
+        let videos = std::mem::take(&mut self.videos);
+        self.less(|c| status_lines(c, &videos));
+        self.videos = videos;
+        self.clear_and_print_videos()
+    }
+
+    fn get_cols_and_start_col(&self) -> (usize, usize) {
+        let full_cols = get_cols();
+        let cols = if self.app_config.split_thumbnail {
+            full_cols / 2
+        } else {
+            full_cols
+        };
+        let start_col = if self.app_config.split_thumbnail {
+            cols
+        } else {
+            0
+        };
+        (cols, start_col)
+    }
+
+    fn info(&mut self) {
+        if self.i < self.toshow.len() {
+            clear();
+            self.print_info(&self.toshow[self.i].clone());
+            self.clear_and_print_videos()
+        }
+    }
+
+    /// Items an action applies to: the visual selection if any, the item
+    /// under the cursor otherwise
+    fn targets(&self) -> Vec<Item> {
+        let mut ids = self.selection.clone();
+        if let Some(start) = self.visual_start {
+            ids.extend(self.visual_range(start).map(|i| self.toshow[i].id.clone()));
+        }
+        if ids.is_empty() {
+            return self.toshow.get(self.i).cloned().into_iter().collect();
+        }
+        let mut rows = self.videos.videos.clone();
+        collapse_duplicates(&mut rows);
+        rows.into_iter()
+            .filter(|row| ids.contains(&row.id))
+            .collect()
+    }
+
+    /// Rows of the current page between the start of `V` and the cursor
+    fn visual_range(&self, start: usize) -> std::ops::RangeInclusive<usize> {
+        let last = self.toshow.len().saturating_sub(1);
+        min(start, self.i).min(last)..=std::cmp::max(start, self.i).min(last)
+    }
+
+    fn is_selected(&self, i: usize, video: &Item) -> bool {
+        self.selection.contains(&video.id)
+            || self
+                .visual_start
+                .is_some_and(|start| self.visual_range(start).contains(&i))
+    }
+
+    fn clear_selection(&mut self) {
+        self.selection.clear();
+        self.visual_start = None;
+    }
+
+    /// Adds or removes the item under the cursor from the selection
+    fn toggle_selected(&mut self) {
+        if let Some(video) = self.toshow.get(self.i) {
+            if !self.selection.remove(&video.id) {
+                self.selection.insert(video.id.clone());
+            }
+            self.down();
+        }
+    }
+
+    /// Starts selecting a range of rows, or adds the range to the selection
+    fn toggle_visual(&mut self) {
+        match self.visual_start.take() {
+            Some(start) => {
+                let ids = self
+                    .visual_range(start)
+                    .map(|i| self.toshow[i].id.clone())
+                    .collect::<Vec<String>>();
+                self.selection.extend(ids);
+            }
+            None => self.visual_start = Some(self.i),
+        }
+        self.clear_and_print_videos();
+    }
+
+    /// Selects every item matched by the filter, on all pages
+    fn select_all(&mut self) {
+        let len = self.videos.videos.len();
+        let matched = to_show_videos(&self.app_config, &mut self.videos, 0, len, &self.filter);
+        self.visual_start = None;
+        self.selection = matched.into_iter().map(|video| video.id).collect();
+        self.clear_and_print_videos();
+        self.debug(&format!(
+            "{} items selected (t to tag them as watched, esc to cancel)",
+            self.selection.len()
+        ));
+    }
+
+    /// Recomputes the current page after items changed
+    fn update_toshow(&mut self) {
+        self.toshow = to_show_videos(
+            &self.app_config,
+            &mut self.videos,
+            self.start,
+            self.start + self.n,
+            &self.filter,
+        );
+    }
+
+    fn flag_items(&mut self, items: &[Item], flag: &Flag) {
+        for item in items {
+            set_flag(&mut self.videos.videos, item, flag);
+        }
+        self.save_videos(&self.app_config, &self.videos);
+        self.update_toshow();
+    }
+
+    fn flag(&mut self, flag: &Flag) {
+        let targets = self.targets();
+        self.flag_items(&targets, flag);
+        self.clear_selection();
+    }
+
+    /// Played or opened items become watched, unless they are starred
+    fn flag_played(&mut self) {
+        if let Some(video) = self.toshow.get(self.i).cloned() {
+            if video.flag != Flag::Starred {
+                self.flag_items(&[video], &Flag::Watched);
+            }
+        }
+    }
+
+    /// Sets the flag of the selected items, or back to unread if they all already have it
+    fn toggle_flag(&mut self, flag: Flag) {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return;
+        }
+        let flag = if targets.iter().all(|video| video.flag.same_state(&flag)) {
+            Flag::Unread
+        } else {
+            flag
+        };
+        self.flag(&flag);
+        self.clear_and_print_videos();
+        if targets.len() > 1 {
+            self.debug(&format!(
+                "tagged {} items as {}",
+                targets.len(),
+                flag.name()
+            ));
+        }
+    }
+
+    fn hide(&mut self) {
+        let targets = self.targets();
+        for item in targets.iter() {
+            set_hidden(&mut self.videos.videos, item, true);
+        }
+        self.save_videos(&self.app_config, &self.videos);
+        self.clear_selection();
+        self.update_toshow();
+        self.clear_and_print_videos();
+        self.debug(&format!(
+            "hid {} items (:unhide to show them back)",
+            targets.len()
+        ));
+    }
+
+    fn unhide_all(&mut self) {
+        for video in self.videos.videos.iter_mut() {
+            video.hidden = false;
+        }
+        self.save_videos(&self.app_config, &self.videos);
+        self.update_toshow();
+    }
+
+    fn download(&mut self) {
+        let targets = self.targets();
+        clear();
+        for (i, item) in targets.iter().enumerate() {
+            println!("downloading {}/{} {}", i + 1, targets.len(), item.title);
+            playback::download(item, &self.app_config, &|s| self.debug(s));
+        }
+        self.clear_selection();
+        self.clear_and_print_videos();
+        self.debug(&format!(
+            "downloaded {} items to {}",
+            targets.len(),
+            self.app_config.video_path
+        ));
+    }
+
+    fn flag_in_progress(&mut self) {
+        if self.i < self.toshow.len() {
+            if let Flag::InProgress { .. } = self.toshow[self.i].flag {
+                return;
+            }
+            let video = self.toshow[self.i].clone();
+            self.flag_items(&[video], &Flag::InProgress { position: 0 });
+            self.clear_and_print_videos();
+        }
+    }
+
+    fn filter_state(&mut self) {
+        move_to_bottom();
+        clear_to_end_of_line();
+        print!("F");
+        flush_stdout();
+        let view_mode = match read_key() {
+            Some(Char('u')) => ViewMode::State(Flag::Unread),
+            Some(Char('p')) => ViewMode::State(Flag::InProgress { position: 0 }),
+            Some(Char('t')) => ViewMode::State(Flag::Watched),
+            Some(Char('x')) => ViewMode::State(Flag::Skipped),
+            Some(Char('*')) => ViewMode::State(Flag::Starred),
+            Some(Char('w')) => ViewMode::State(Flag::WatchLater),
+            Some(Char('a')) => ViewMode::All,
+            _ => {
+                self.debug("unknown state (u,p,t,x,*,w or a for all)");
+                return;
+            }
+        };
+        self.set_view_mode(view_mode);
+    }
+
+    fn set_view_mode(&mut self, view_mode: ViewMode) {
+        self.videos.view_mode = view_mode;
+        self.save_videos(&self.app_config, &self.videos);
+        self.move_page(0);
+        self.debug(&format!("showing {} items", self.videos.view_mode.name()));
+    }
+
+    fn help(&mut self) {
+        clear();
+        print_help();
+        self.wait_key_press_and_clear_and_print_videos()
+    }
+
+    fn jump(&mut self, new_i: usize) {
+        self.i = new_i;
+        self.clear_and_print_videos();
+    }
+
+    fn up(&mut self) {
+        self.jump(if self.i > 0 { self.i - 1 } else { self.n - 1 });
+    }
+
+    fn down(&mut self) {
+        self.jump(self.i + 1);
+    }
+
+    fn handle_resize(&mut self) {
+        let lines = get_lines();
+        if self.n != lines {
+            self.n = lines;
+            self.i = 0;
+            self.clear_and_print_videos();
+        }
+    }
+
+    async fn load_videos_from_cache(&mut self) {
+        match self.load(false).await {
+            Some(videos) => {
+                self.videos = videos;
+                self.modified = SystemTime::now();
+            }
+            None => self.debug("no video to load"),
+        };
+    }
+
+    fn clear_selector(&self, i: usize, col_width: usize) {
+        let (_, start_col) = self.get_cols_and_start_col();
+        move_cursor(i, start_col);
+        print!(" ");
+        move_cursor(i, col_width + start_col);
+        print!(" ");
+        flush_stdout();
+    }
+
+    fn print_selector(&self, i: usize, col_width: usize) {
+        let (_, start_col) = self.get_cols_and_start_col();
+        move_cursor(i, start_col);
+        print!("\x1b[1m|\x1b[0m\r");
+        move_cursor(i, col_width + start_col);
+        print!("\x1b[1m|\x1b[0m\r");
+        flush_stdout();
+    }
+
+    async fn run(&mut self, sender: mpsc::Sender<()>, mut receiver: mpsc::Receiver<()>) {
+        self.load_videos_from_cache().await;
+        self.start = 0;
+        self.i = 0;
+        smcup();
+        self.first_page();
+        self.clear_and_print_videos();
+        hide_cursor();
+        let mut numbers: Vec<i64> = vec![];
+        let mut reload = false;
+        loop {
+            if reload || receiver.try_recv().is_ok() {
+                self.soft_reload().await;
+                self.debug("reload done");
+            }
+            self.check_playbacks();
+            if self.videos.videos.is_empty() {
+                self.help();
+            }
+            self.handle_resize();
+            self.print_selector(self.i, self.col_width);
+            let input = input();
+            let result;
+            {
+                let _ = input.enable_mouse_mode();
+                let _screen = RawScreen::into_raw_mode();
+                // polled rather than blocking, so that a reload or a player
+                // exiting updates the list without waiting for a key press
+                let mut stdin = input.read_async();
+                result = loop {
+                    if let Some(event) = stdin.next() {
+                        break Some(event);
+                    }
+                    reload = receiver.try_recv().is_ok();
+                    if reload || self.poll_playbacks() {
+                        break None;
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                };
+                let _ = input.disable_mouse_mode();
+            }
+            match result {
+                None => (),
+                Some(key_event) => match key_event {
+                    InputEvent::Keyboard(event) => match event {
+                        Char(x) if x.is_ascii_digit() => {
+                            if let Some(digit) = x.to_digit(10) {
+                                numbers.push(digit as i64)
+                            }
+                        }
+                        _ => {
+                            let n = numbers.iter().fold(0, |acc, digit| acc * 10 + digit);
+                            let n = if n == 0 { 1 } else { n };
+                            let mut quitting = false;
+                            for _ in 0..n {
+                                match event {
+                                    Ctrl('c') => {
+                                        self.check_playbacks();
+                                        quit();
+                                        quitting = true;
+                                    }
+                                    Char('q') => {
+                                        self.drain_playbacks().await;
+                                        quit();
+                                        quitting = true;
+                                    }
+                                    Char('c') => self.download_subscriptions(),
+                                    Char('j') | Char('l') | Down => {
+                                        self.down();
+                                        let _ = self.current_thumbnail_to_thumbnail_jpg().await;
+                                    }
+                                    Char('k') | Up => {
+                                        self.up();
+                                        let _ = self.current_thumbnail_to_thumbnail_jpg().await;
+                                    }
+                                    Char('g') | Char('H') => {
+                                        self.jump(0);
+                                        let _ = self.current_thumbnail_to_thumbnail_jpg().await;
+                                    }
+                                    Char('M') => {
+                                        self.jump(self.n / 2);
+                                        let _ = self.current_thumbnail_to_thumbnail_jpg().await;
+                                    }
+                                    Char('G') | Char('L') => {
+                                        self.jump(self.n - 1);
+                                        let _ = self.current_thumbnail_to_thumbnail_jpg().await;
+                                    }
+                                    Char('r') | Char('$') | Left => self.soft_reload().await,
+                                    Char('P') => {
+                                        self.previous_page();
+                                        let _ = self.current_thumbnail_to_thumbnail_jpg().await;
+                                    }
+                                    Char('N') => {
+                                        self.next_page();
+                                        let _ = self.current_thumbnail_to_thumbnail_jpg().await;
+                                    }
+                                    Char('R') => {
+                                        tokio::spawn(hard_reload_bg(
+                                            sender.clone(),
+                                            self.app_config.clone(),
+                                        ));
+                                    }
+                                    Char('h') | Char('?') => self.help(),
+                                    Char('i') | Right => self.info(),
+                                    Char('S') => self.channel_status(),
+                                    Char('t') => self.toggle_flag(Flag::Watched),
+                                    Char('x') => self.toggle_flag(Flag::Skipped),
+                                    Char('*') => self.toggle_flag(Flag::Starred),
+                                    Char('w') => self.toggle_flag(Flag::WatchLater),
+                                    Char('I') => self.flag_in_progress(),
+                                    Char('U') => {
+                                        self.flag(&Flag::Unread);
+                                        self.clear_and_print_videos();
+                                    }
+                                    Char('F') => self.filter_state(),
+                                    KeyEvent::Tab => self.cycle_view(true),
+                                    Char('z') => {
+                                        self.app_config.sort_key = self.app_config.sort_key.next();
+                                        self.move_page(0);
+                                        self.debug(&format!(
+                                            "sorted by {}",
+                                            self.app_config.sort_key.name()
+                                        ));
+                                    }
+                                    KeyEvent::BackTab => self.cycle_view(false),
+                                    Char('\'') => self.views_menu(),
+                                    Char('m') => self.set_view_mode(self.videos.view_mode.next()),
+                                    Char('#') => {
+                                        self.app_config.hide_shorts = !self.app_config.hide_shorts;
+                                        self.move_page(0);
+                                        self.debug(if self.app_config.hide_shorts {
+                                            "shorts hidden"
+                                        } else {
+                                            "shorts shown"
+                                        });
+                                    }
+                                    Char('e') => self.toggle_queued(),
+                                    Char('v') => self.toggle_selected(),
+                                    Char('V') => self.toggle_visual(),
+                                    Char('A') => self.select_all(),
+                                    KeyEvent::Esc => {
+                                        self.clear_selection();
+                                        self.clear_and_print_videos();
+                                    }
+                                    Char('D') => self.download(),
+                                    Char('X') => self.hide(),
+                                    Char('Q') => self.queue(),
+                                    Char('T') => match self.display_current_thumbnail().await {
+                                        Ok(_) => {}
+                                        Err(e) => self.debug(&format!("error: {:?}", e)),
+                                    },
+                                    Char('p') | KeyEvent::Enter => self.play_current(false),
+                                    Char('a') => self.play_current(true),
+                                    Char('o') => self.open_current(false),
+                                    Char('O') => self.open_current(true),
+                                    Char('/') => self.search(),
+                                    Char('n') => self.search_next(),
+                                    Ctrl('f') => self.find(),
+                                    Char(']') => self.jump_to_next_new(),
+                                    Char(':') => self.command().await,
+                                    Char('y') => self.yank_video_uri(),
+                                    Char('s') => {
+                                        self.app_config.split_thumbnail =
+                                            !self.app_config.split_thumbnail;
+                                        self.clear_and_print_videos();
+                                    }
+                                    Char('f') | Char('|') => self.filter(),
+                                    _ => self.debug("key not supported (press h for help)"),
+                                }
+                                numbers = vec![];
+                            }
+                            if quitting {
+                                break;
+                            }
+                        }
+                    },
+                    InputEvent::Mouse(event) => match event {
+                        MouseEvent::Press(MouseButton::Left, _x, y) => {
+                            let new_i = usize::from(y) - 1;
+                            if self.i == new_i {
+                                self.play_current(false);
+                            } else {
+                                self.jump(new_i);
+                            }
+                        }
+                        MouseEvent::Press(MouseButton::WheelUp, _x, _y) => self.up(),
+                        MouseEvent::Press(MouseButton::WheelDown, _x, _y) => self.down(),
+                        _ => (),
+                    },
+                    _ => (),
+                },
+            }
+            self.i %= self.n;
+        }
+    }
+}
+
+fn build_yts(app_config: AppConfig, config_path: Option<String>) -> YoutubeSubscribtions {
+    YoutubeSubscribtions {
+        modified: SystemTime::now(),
+        col_width: 0,
+        n: 0,
+        start: 0,
+        search: Regex::new("").unwrap(),
+        filter: Query::all(),
+        i: 0,
+        toshow: vec![],
+        videos: Items {
+            ..Default::default()
+        },
+        filter_chars: vec![],
+        playbacks: vec![],
+        finished_playbacks: vec![],
+        selection: HashSet::new(),
+        visual_start: None,
+        active_view: None,
+        filter_text: String::new(),
+        default_sort: app_config.sort.clone(),
+        default_sort_key: app_config.sort_key,
+        app_config,
+        config_path,
+    }
+}
+
+async fn hard_reload_bg(sender: mpsc::Sender<()>, app_config: AppConfig) {
+    let mut yts = build_yts(app_config, None);
+    // flags and queue are carried over from the cache
+    yts.videos = load_cache(&yts.app_config).unwrap_or_default();
+    yts.hard_reload().await;
+    let _ = sender.send(()).await;
+}
+
+pub async fn run(app_config: AppConfig, config_path: Option<String>) {
+    let _ = ctrlc::set_handler(move || {
+        quit();
+        std::process::exit(0);
+    });
+    let (sender, receiver) = mpsc::channel::<()>(1);
+    build_yts(app_config, config_path)
+        .run(sender, receiver)
+        .await;
+}
@@ -0,0 +1,287 @@
+mod common;
+
+use common::items;
+use youtube_subscriptions::cache::{
+    channel_item_ids, collapse_duplicates, has_id, mark_read, merge_state, move_queued,
+    new_items_summary, published_before, queued_items, set_flag, set_hidden, set_short_kinds,
+    to_show_videos, toggle_queued, Items, SortKey, ViewMode,
+};
+use youtube_subscriptions::config::{AppConfig, ChannelSettings};
+use youtube_subscriptions::feed::{Flag, Item, ItemKind};
+use youtube_subscriptions::query::Query;
+
+#[test]
+fn collapses_copies_with_the_same_id() {
+    let mut videos = items("youtube.xml", "https://www.youtube.com/feeds/videos.xml");
+    videos.extend(items("youtube.xml", "https://yewtu.be/feed/channel"));
+    collapse_duplicates(&mut videos);
+    assert_eq!(videos.len(), 2);
+}
+
+#[test]
+fn collapses_copies_with_the_same_title_and_duration() {
+    let mut videos = items("podcast.xml", "https://latenightlinux.com/feed");
+    videos.extend(items(
+        "podcast_mirror.xml",
+        "https://mirror.example.net/feed",
+    ));
+    let mut rows = videos.clone();
+    collapse_duplicates(&mut rows);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].channel, "Late Night Linux");
+    assert_eq!(rows[0].sources, vec!["Linux Podcasts Mirror".to_string()]);
+    assert_eq!(rows[0].aliases, vec!["guid:mirror-300".to_string()]);
+
+    set_flag(&mut videos, &rows[0], &Flag::Watched);
+    assert!(videos.iter().all(|video| video.flag == Flag::Watched));
+}
+
+#[test]
+fn reads_flags_of_older_caches() {
+    let item = r#"{"kind": "Video", "channel_url": "c", "channel": "C", "title": "T",
+        "url": "u", "published": "2024-01-01T00:00:00+00:00", "description": "", "flag": "#;
+    let flag = |json: &str| {
+        serde_json::from_str::<Item>(&format!("{}{}}}", item, json))
+            .unwrap()
+            .flag
+    };
+    assert_eq!(flag("null"), Flag::Unread);
+    assert_eq!(flag(r#""Read""#), Flag::Watched);
+    assert_eq!(
+        flag(r#"{"InProgress": {"position": 90}}"#),
+        Flag::InProgress { position: 90 }
+    );
+}
+
+#[test]
+fn keeps_a_queue_of_items() {
+    let mut videos = Items {
+        videos: items("youtube.xml", "https://www.youtube.com/feeds/videos.xml"),
+        ..Default::default()
+    };
+    let (first, second) = (videos.videos[0].clone(), videos.videos[1].clone());
+    assert!(toggle_queued(&mut videos, &first));
+    assert!(toggle_queued(&mut videos, &second));
+    assert_eq!(move_queued(&mut videos, 1, -1), 0);
+    assert_eq!(move_queued(&mut videos, 0, -1), 0);
+    let queued = queued_items(&videos);
+    assert_eq!(queued[0].id, second.id);
+    assert_eq!(queued[1].id, first.id);
+
+    let mut refreshed = Items {
+        videos: vec![first.clone()],
+        ..Default::default()
+    };
+    merge_state(&mut refreshed, &videos, "2024-01-01T00:00:00+00:00");
+    assert_eq!(refreshed.queue, vec![first.id.clone()]);
+    assert!(!toggle_queued(&mut refreshed, &first));
+    assert!(refreshed.queue.is_empty());
+}
+
+#[test]
+fn leaves_hidden_items_out() {
+    let mut videos = items("podcast.xml", "https://latenightlinux.com/feed");
+    videos.extend(items(
+        "podcast_mirror.xml",
+        "https://mirror.example.net/feed",
+    ));
+    let mut rows = videos.clone();
+    collapse_duplicates(&mut rows);
+    set_hidden(&mut videos, &rows[0], true);
+    assert!(videos.iter().all(|video| video.hidden));
+    let mut videos = Items {
+        videos,
+        ..Default::default()
+    };
+    let shown = to_show_videos(&AppConfig::default(), &mut videos, 0, 10, &Query::all());
+    assert!(shown.is_empty());
+}
+
+#[test]
+fn marks_items_published_before_a_date_as_read() {
+    let mut videos = items("youtube.xml", "https://www.youtube.com/feeds/videos.xml");
+    videos.extend(items("atom.xml", "https://blog.example.com/feed"));
+    videos[0].flag = Flag::Starred;
+    videos[2].flag = Flag::Skipped;
+    let date = chrono::NaiveDate::from_ymd_opt(2100, 1, 1).unwrap();
+    assert!(videos.iter().all(|video| published_before(video, &date)));
+    assert_eq!(
+        mark_read(&mut videos, |video| published_before(video, &date)),
+        videos.len() - 2
+    );
+    assert_eq!(videos[0].flag, Flag::Starred);
+    assert_eq!(videos[1].flag, Flag::Watched);
+    assert_eq!(videos[2].flag, Flag::Skipped);
+    let date = chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+    assert!(!videos.iter().any(|video| published_before(video, &date)));
+}
+
+#[test]
+fn marks_items_of_a_channel_as_read() {
+    let mirror = "https://mirror.example.com/feed";
+    let mut videos = items("youtube.xml", "https://www.youtube.com/feeds/videos.xml");
+    let mut copy = videos[0].clone();
+    copy.channel_url = mirror.to_string();
+    copy.channel = "Mirror".to_string();
+    videos.push(copy);
+    let ids = channel_item_ids(&videos, mirror);
+    assert_eq!(mark_read(&mut videos, |video| has_id(video, &ids)), 1);
+    assert_eq!(videos[0].flag, Flag::Watched);
+    assert_eq!(videos[1].flag, Flag::Unread);
+    assert_eq!(videos.last().unwrap().flag, Flag::Watched);
+}
+
+#[test]
+fn shows_items_of_the_view_mode() {
+    let mut videos = Items {
+        videos: items("youtube.xml", "https://www.youtube.com/feeds/videos.xml"),
+        ..Default::default()
+    };
+    videos.videos[0].flag = Flag::Watched;
+    videos.videos[1].flag = Flag::Starred;
+    let mut shown = |view_mode: ViewMode| {
+        videos.view_mode = view_mode;
+        to_show_videos(&AppConfig::default(), &mut videos, 0, 10, &Query::all()).len()
+    };
+    assert_eq!(shown(ViewMode::All), 2);
+    assert_eq!(shown(ViewMode::Unread), 1);
+    assert_eq!(shown(ViewMode::Starred), 1);
+    assert_eq!(shown(ViewMode::InProgress), 0);
+    assert_eq!(shown(ViewMode::Queued), 0);
+    assert_eq!(shown(ViewMode::State(Flag::Watched)), 1);
+    assert_eq!(ViewMode::Queued.next(), ViewMode::All);
+}
+
+#[test]
+fn sorts_items_by_key() {
+    let mut videos = Items {
+        videos: items("youtube.xml", "https://www.youtube.com/feeds/videos.xml"),
+        ..Default::default()
+    };
+    videos
+        .videos
+        .extend(items("podcast.xml", "https://latenightlinux.com/feed"));
+    let mut app_config = AppConfig {
+        sort: "asc".to_string(),
+        ..Default::default()
+    };
+    let mut channels = |sort_key: SortKey| {
+        app_config.sort_key = sort_key;
+        to_show_videos(&app_config, &mut videos, 0, 10, &Query::all())
+            .into_iter()
+            .map(|video| video.channel_url)
+            .collect::<Vec<String>>()
+    };
+    let by_channel = channels(SortKey::Channel);
+    assert_eq!(by_channel[0], "https://latenightlinux.com/feed");
+    let round_robin = channels(SortKey::RoundRobin);
+    assert_ne!(round_robin[0], round_robin[1]);
+    assert_eq!(round_robin.len(), 3);
+    let by_duration = channels(SortKey::Duration);
+    assert_eq!(by_duration[0], "https://latenightlinux.com/feed");
+}
+
+#[test]
+fn applies_channel_settings() {
+    let youtube = "http://www.youtube.com/feeds/videos.xml?channel_id=UCXuqSBlHAE6Xw-yeJA0Tunw";
+    let podcast = "https://latenightlinux.com/feed";
+    let mut videos = Items {
+        videos: items("podcast.xml", podcast),
+        last_refresh: Some("2026-10-01T00:00:00+02:00".to_string()),
+        ..Default::default()
+    };
+    videos.videos.extend(items("youtube.xml", youtube));
+    for video in videos.videos.iter_mut() {
+        video.first_seen = videos.last_refresh.clone();
+    }
+    let mut app_config = AppConfig {
+        sort: "asc".to_string(),
+        ..Default::default()
+    };
+    app_config.channels.insert(
+        "UCXuqSBlHAE6Xw-yeJA0Tunw".to_string(),
+        ChannelSettings {
+            name: Some("LTT".to_string()),
+            priority: 1,
+            kind: Some(ItemKind::Audio),
+            blocked_keywords: vec!["QUIETEST".to_string()],
+            youtubedl_format: Some("best".to_string()),
+            player_additional_opts: vec!["--speed=1.5".to_string()],
+            muted: true,
+            ..Default::default()
+        },
+    );
+    let shown = to_show_videos(&app_config, &mut videos, 0, 10, &Query::all());
+    assert_eq!(shown.len(), 2);
+    assert_eq!(shown[0].title, "This GPU review is different");
+    assert_eq!(shown[0].channel, "LTT");
+    assert!(matches!(shown[0].kind, ItemKind::Audio));
+    assert_eq!(
+        to_show_videos(
+            &app_config,
+            &mut videos,
+            0,
+            10,
+            &Query::parse("channel:ltt").unwrap()
+        )
+        .len(),
+        1
+    );
+    assert_eq!(
+        new_items_summary(&app_config, &videos),
+        "1 new: Late Night Linux 1"
+    );
+
+    let channel_config = app_config.for_channel(youtube);
+    assert_eq!(channel_config.youtubedl_format, "best");
+    assert_eq!(channel_config.player_additional_opts, vec!["--speed=1.5"]);
+    assert_eq!(
+        app_config.for_channel(podcast).youtubedl_format,
+        app_config.youtubedl_format
+    );
+
+    app_config.channels.insert(
+        podcast.to_string(),
+        ChannelSettings {
+            hidden: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        to_show_videos(&app_config, &mut videos, 0, 10, &Query::all()).len(),
+        1
+    );
+}
+
+#[test]
+fn keeps_shorts_checks() {
+    let url = "https://www.youtube.com/feeds/videos.xml";
+    let mut original = Items {
+        videos: items("youtube.xml", url),
+        ..Default::default()
+    };
+    let short = original.videos[1].id.clone();
+    original.shorts.insert(short.clone(), true);
+    original.shorts.insert(original.videos[0].id.clone(), false);
+    original.shorts.insert("gone".to_string(), true);
+
+    let mut videos = Items {
+        videos: items("youtube.xml", url),
+        ..Default::default()
+    };
+    merge_state(&mut videos, &original, "2026-10-01T00:00:00+02:00");
+    assert_eq!(videos.shorts.len(), 2);
+    set_short_kinds(&mut videos);
+    assert!(matches!(videos.videos[1].kind, ItemKind::Short));
+    assert!(!matches!(videos.videos[0].kind, ItemKind::Short));
+
+    let mut app_config = AppConfig::default();
+    assert_eq!(
+        to_show_videos(&app_config, &mut videos, 0, 10, &Query::all()).len(),
+        2
+    );
+    app_config.hide_shorts = true;
+    let shown = to_show_videos(&app_config, &mut videos, 0, 10, &Query::all());
+    assert_eq!(shown.len(), 1);
+    assert_ne!(shown[0].id, short);
+}
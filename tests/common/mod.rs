@@ -0,0 +1,19 @@
+// each test crate uses some of these helpers
+#![allow(dead_code)]
+
+use youtube_subscriptions::feed::{parse_feed, Item};
+
+pub fn fixture(name: &str) -> String {
+    std::fs::read_to_string(format!(
+        "{}/tests/fixtures/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    ))
+    .expect("fixture")
+}
+
+pub fn items(fixture_name: &str, channel_url: &str) -> Vec<Item> {
+    parse_feed(&fixture(fixture_name), channel_url)
+        .unwrap()
+        .items
+}
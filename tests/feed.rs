@@ -0,0 +1,169 @@
+mod common;
+
+use common::fixture;
+use youtube_subscriptions::feed::{
+    detect_format, parse_feed, parse_json_feed, FeedError, FeedFormat, ItemKind,
+};
+
+#[test]
+fn parses_youtube_feed() {
+    let url = "http://www.youtube.com/feeds/videos.xml?channel_id=UCXuqSBlHAE6Xw-yeJA0Tunw";
+    let feed = parse_feed(&fixture("youtube.xml"), url).unwrap();
+    assert_eq!(feed.title, "Linus Tech Tips");
+    assert_eq!(feed.items.len(), 2);
+    let item = &feed.items[0];
+    assert!(matches!(item.kind, ItemKind::Video));
+    assert_eq!(item.channel, "Linus Tech Tips");
+    assert_eq!(item.channel_url, url);
+    assert_eq!(item.title, "This GPU review is different");
+    assert_eq!(item.url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+    assert_eq!(item.id, "youtube:dQw4w9WgXcQ");
+    assert_eq!(item.published, "2026-09-30T17:00:06+00:00");
+    assert_eq!(item.description, "We tested every card we could find.");
+    assert_eq!(
+        item.thumbnail,
+        "https://i3.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg"
+    );
+}
+
+#[test]
+fn parses_podcast_feed() {
+    let feed = parse_feed(&fixture("podcast.xml"), "https://latenightlinux.com/feed").unwrap();
+    assert_eq!(feed.title, "Late Night Linux");
+    assert_eq!(feed.items.len(), 1);
+    let item = &feed.items[0];
+    assert!(matches!(item.kind, ItemKind::Audio));
+    assert_eq!(item.url, "https://latenightlinux.com/audio/lnl300.mp3");
+    assert_eq!(item.id, "guid:lnl-300");
+    assert_eq!(item.duration, Some(45 * 60 + 12));
+    assert_eq!(item.published, "2026-09-28T07:00:00+00:00");
+    assert_eq!(
+        item.content.as_deref(),
+        Some("<p>The <b>300th</b> episode</p>")
+    );
+}
+
+#[test]
+fn parses_rdf_feed() {
+    let feed = parse_feed(&fixture("rdf.xml"), "https://lwn.net/headlines/rss").unwrap();
+    assert_eq!(feed.format, FeedFormat::Rss1);
+    assert_eq!(feed.title, "LWN.net");
+    assert_eq!(feed.items.len(), 1);
+    let item = &feed.items[0];
+    assert!(matches!(item.kind, ItemKind::Other));
+    assert_eq!(item.title, "Kernel release status");
+    assert_eq!(item.url, "https://lwn.net/Articles/990001/");
+    assert_eq!(item.id, "url:lwn.net/Articles/990001");
+    assert_eq!(item.published, "2026-09-29T14:10:00+00:00");
+    assert_eq!(
+        item.description,
+        "The current development kernel is 6.19-rc2."
+    );
+    assert_eq!(
+        item.content.as_deref(),
+        Some("<p>The current development kernel</p>")
+    );
+}
+
+#[test]
+fn parses_atom_feed() {
+    let feed = parse_feed(&fixture("atom.xml"), "https://blog.example.com/feed").unwrap();
+    assert_eq!(feed.format, FeedFormat::Atom);
+    assert_eq!(feed.title, "Example Blog");
+    assert_eq!(feed.items.len(), 2);
+    let episode = &feed.items[0];
+    assert!(matches!(episode.kind, ItemKind::Audio));
+    assert_eq!(episode.url, "https://blog.example.com/episode-12.ogg");
+    assert_eq!(episode.published, "2026-09-27T10:00:00Z");
+    assert_eq!(episode.description, "Notes for episode twelve.");
+    let post = &feed.items[1];
+    assert!(matches!(post.kind, ItemKind::Other));
+    assert_eq!(post.url, "https://blog.example.com/plain-post");
+}
+
+#[test]
+fn parses_json_feed() {
+    let url = "https://bridge.example.org/feed.json";
+    let feed = parse_feed(&fixture("jsonfeed.json"), url).unwrap();
+    assert_eq!(feed.format, FeedFormat::JsonFeed);
+    assert_eq!(feed.title, "Bridge Radio");
+    assert_eq!(feed.items.len(), 2);
+    let show = &feed.items[0];
+    assert!(matches!(show.kind, ItemKind::Audio));
+    assert_eq!(show.title, "Show 42");
+    assert_eq!(show.url, "https://bridge.example.org/shows/42.mp3");
+    assert_eq!(show.id, "url:bridge.example.org/shows/42");
+    assert_eq!(show.duration, Some(3600));
+    assert_eq!(show.published, "2026-09-26T20:00:00+02:00");
+    assert_eq!(show.description, "Live from the bridge.");
+    assert_eq!(show.thumbnail, "https://bridge.example.org/shows/42.jpg");
+    assert_eq!(
+        show.content.as_deref(),
+        Some("<p>Live from <em>the bridge</em>.</p>")
+    );
+    let note = &feed.items[1];
+    assert!(matches!(note.kind, ItemKind::Other));
+    assert_eq!(note.url, "https://bridge.example.org/notes/7");
+    assert_eq!(note.description, "No show next week.");
+    assert!(matches!(
+        parse_json_feed(r#"{"title": "Empty", "items": []}"#, url),
+        Err(FeedError::NoEntries)
+    ));
+}
+
+#[test]
+fn detects_feed_formats() {
+    assert_eq!(
+        detect_format(&fixture("youtube.xml")).unwrap(),
+        FeedFormat::Atom
+    );
+    assert_eq!(
+        detect_format(&fixture("atom.xml")).unwrap(),
+        FeedFormat::Atom
+    );
+    assert_eq!(
+        detect_format(&fixture("podcast.xml")).unwrap(),
+        FeedFormat::Rss2
+    );
+    assert_eq!(
+        detect_format(&fixture("rdf.xml")).unwrap(),
+        FeedFormat::Rss1
+    );
+    assert_eq!(
+        detect_format(r#"{"version": "https://jsonfeed.org/version/1.1", "items": []}"#).unwrap(),
+        FeedFormat::JsonFeed
+    );
+    assert!(matches!(
+        detect_format(&fixture("unknown.xml")),
+        Err(FeedError::UnknownFormat)
+    ));
+    assert!(matches!(
+        detect_format(r#"<feed xmlns="https://example.com/ns"><title>t</title></feed>"#),
+        Err(FeedError::UnknownFormat)
+    ));
+    assert!(matches!(
+        detect_format(r#"<RDF><channel><title>t</title></channel></RDF>"#),
+        Err(FeedError::UnknownFormat)
+    ));
+}
+
+#[test]
+fn reports_feed_errors() {
+    let url = "https://example.com/feed";
+    assert!(matches!(
+        parse_feed(&fixture("invalid.xml"), url),
+        Err(FeedError::Xml(_))
+    ));
+    assert!(matches!(
+        parse_feed(&fixture("no_title.xml"), url),
+        Err(FeedError::MissingChannelTitle)
+    ));
+    assert!(matches!(
+        parse_feed(&fixture("no_entries.xml"), url),
+        Err(FeedError::NoEntries)
+    ));
+    assert!(matches!(
+        parse_feed(&fixture("unknown.xml"), url),
+        Err(FeedError::UnknownFormat)
+    ));
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::items;
+use youtube_subscriptions::cache::Items;
+use youtube_subscriptions::feed::Flag;
+use youtube_subscriptions::fetch::{client, get_channel_videos};
+use youtube_subscriptions::rules::Rules;
+
+#[tokio::test]
+async fn keeps_items_of_channels_which_failed_loading() {
+    // nothing listens on port 1
+    let channel_url = "http://127.0.0.1:1/feed.xml";
+    let mut videos = items("podcast.xml", channel_url);
+    videos[0].flag = Flag::Starred;
+    videos.extend(items("atom.xml", "https://blog.example.com/feed"));
+    let original = Items {
+        videos,
+        ..Default::default()
+    };
+    let fetched = get_channel_videos(
+        &client(),
+        channel_url.to_string(),
+        Some(&"\"etag\"".to_string()),
+        &original,
+        &Rules::new(&[], &|_| ()),
+        &|_| (),
+    )
+    .await;
+    assert!(!fetched.status.is_ok());
+    assert_eq!(fetched.etag, None);
+    assert_eq!(fetched.videos.len(), 1);
+    assert_eq!(fetched.videos[0].flag, Flag::Starred);
+}
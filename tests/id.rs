@@ -0,0 +1,94 @@
+use youtube_subscriptions::id::{entry_id, item_id, normalize_url};
+
+#[test]
+fn youtube_ids_are_shared_across_mirrors() {
+    let id = "youtube:dQw4w9WgXcQ";
+    assert_eq!(
+        item_id("https://www.youtube.com/watch?v=dQw4w9WgXcQ", None),
+        id
+    );
+    assert_eq!(
+        item_id("https://yewtu.be/watch?v=dQw4w9WgXcQ&t=42", None),
+        id
+    );
+    assert_eq!(item_id("https://youtu.be/dQw4w9WgXcQ", None), id);
+    assert_eq!(
+        item_id("https://www.youtube.com/shorts/dQw4w9WgXcQ", None),
+        id
+    );
+    assert_eq!(
+        item_id("https://example.com/post", Some("yt:video:dQw4w9WgXcQ")),
+        id
+    );
+}
+
+#[test]
+fn peertube_ids_use_the_video_uuid() {
+    let id = "peertube:9c9de5e8-0a1e-484a-b099-e80766180a6d";
+    assert_eq!(
+        item_id(
+            "https://framatube.org/videos/watch/9c9de5e8-0a1e-484a-b099-e80766180a6d",
+            None
+        ),
+        id
+    );
+    assert_eq!(
+        item_id("https://tube.example.org/w/kkGMgK9ZtnKfYAgnEtQxbv", None),
+        id
+    );
+    assert_eq!(
+        item_id("https://wiki.example.org/w/MainPageForThisWebSite", None),
+        "url:wiki.example.org/w/MainPageForThisWebSite"
+    );
+    assert_eq!(
+        item_id("https://example.org/blog/w/kkGMgK9ZtnKfYAgnEtQxbv", None),
+        "url:example.org/blog/w/kkGMgK9ZtnKfYAgnEtQxbv"
+    );
+    assert_eq!(
+        item_id("https://example.org/w/about", None),
+        "url:example.org/w/about"
+    );
+}
+
+#[test]
+fn other_ids_use_guid_or_normalized_url() {
+    assert_eq!(
+        item_id(
+            "https://latenightlinux.com/audio/lnl300.mp3",
+            Some("lnl-300")
+        ),
+        "guid:lnl-300"
+    );
+    assert_eq!(
+        item_id(
+            "http://blog.example.com/post/?utm_source=rss&page=2#top",
+            None
+        ),
+        item_id("https://www.blog.example.com/post?page=2", None)
+    );
+    assert_eq!(
+        normalize_url("https://www.Example.com/a/?fbclid=x&b=1").as_deref(),
+        Some("example.com/a?b=1")
+    );
+}
+
+#[test]
+fn entries_without_url_nor_guid_get_distinct_ids() {
+    let feed = "https://example.com/feed";
+    let first = entry_id("", None, feed, "First", "2026-09-01T00:00:00Z");
+    let second = entry_id("", Some(" "), feed, "Second", "2026-09-01T00:00:00Z");
+    assert!(first.starts_with("hash:"));
+    assert_ne!(first, second);
+    assert_eq!(
+        first,
+        entry_id("", None, feed, "First", "2026-09-01T00:00:00Z")
+    );
+    assert_eq!(
+        entry_id("https://example.com/post", None, feed, "First", ""),
+        item_id("https://example.com/post", None)
+    );
+    assert_eq!(
+        entry_id("", Some("lnl-300"), feed, "First", ""),
+        "guid:lnl-300"
+    );
+}
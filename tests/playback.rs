@@ -0,0 +1,117 @@
+use youtube_subscriptions::cache::Items;
+use youtube_subscriptions::config::AppConfig;
+use youtube_subscriptions::feed::{Flag, Item};
+use youtube_subscriptions::playback::{
+    download_path, progress_flag, record_progress, save_progress, PlaybackProgress,
+};
+
+fn progress(position: u64, duration: Option<u64>) -> PlaybackProgress {
+    PlaybackProgress {
+        ids: vec!["youtube:aaaaaaaaaaa".to_string()],
+        position,
+        duration,
+    }
+}
+
+#[test]
+fn flags_items_from_playback_progress() {
+    let app_config = AppConfig::default();
+    let unread = Flag::Unread;
+    assert_eq!(
+        progress_flag(&unread, &progress(600, Some(1000)), &app_config),
+        Flag::InProgress { position: 600 }
+    );
+    assert_eq!(
+        progress_flag(&unread, &progress(950, Some(1000)), &app_config),
+        Flag::Watched
+    );
+    assert_eq!(
+        progress_flag(&unread, &progress(2, Some(1000)), &app_config),
+        Flag::Unread
+    );
+    assert_eq!(
+        progress_flag(&unread, &progress(600, None), &app_config),
+        Flag::InProgress { position: 600 }
+    );
+    assert_eq!(
+        progress_flag(&Flag::Starred, &progress(950, Some(1000)), &app_config),
+        Flag::Starred
+    );
+}
+
+fn item() -> Item {
+    serde_json::from_str(
+        r#"{"kind": "Video", "channel_url": "", "channel": "", "title": "",
+            "url": "https://www.youtube.com/watch?v=aaaaaaaaaaa", "published": "",
+            "description": "", "thumbnail": "", "flag": null, "content": null,
+            "id": "youtube:aaaaaaaaaaa"}"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn records_progress_on_all_copies() {
+    let app_config = AppConfig::default();
+    let item = item();
+    let mut videos = Items {
+        queue: vec![item.id.clone()],
+        videos: vec![item.clone(), item],
+        ..Default::default()
+    };
+    record_progress(&mut videos, &progress(300, Some(1000)), &app_config);
+    for video in videos.videos.iter() {
+        assert_eq!(video.flag, Flag::InProgress { position: 300 });
+        assert_eq!(video.duration, Some(1000));
+    }
+    assert_eq!(videos.queue.len(), 1);
+    record_progress(&mut videos, &progress(1000, Some(1000)), &app_config);
+    assert_eq!(videos.videos[0].flag, Flag::Watched);
+    assert!(videos.queue.is_empty());
+}
+
+#[test]
+fn saves_progress_without_losing_items() {
+    let dir = std::env::temp_dir().join(format!("yts-playback-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let app_config = AppConfig {
+        cache_path: dir.join("cache.json").to_str().unwrap().to_string(),
+        ..Default::default()
+    };
+    let mut videos = Items {
+        videos: vec![item()],
+        ..Default::default()
+    };
+    // no cache written yet, the items played are kept
+    save_progress(&mut videos, &progress(300, Some(1000)), &app_config).unwrap();
+    assert_eq!(videos.videos.len(), 1);
+    assert_eq!(videos.videos[0].flag, Flag::InProgress { position: 300 });
+    // the cache is read back for the next progress
+    save_progress(&mut videos, &progress(1000, Some(1000)), &app_config).unwrap();
+    assert_eq!(videos.videos.len(), 1);
+    assert_eq!(videos.videos[0].flag, Flag::Watched);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn finds_downloads_of_earlier_versions() {
+    let dir = std::env::temp_dir().join(format!("yts-downloads-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let app_config = AppConfig {
+        video_path: dir.to_str().unwrap().to_string(),
+        ..Default::default()
+    };
+    let url = "https://www.youtube.com/watch?v=aaaaaaaaaaa";
+    assert_eq!(
+        download_path(url, &app_config),
+        format!(
+            "{}/aHR0cHM6Ly93d3cueW91dHViZS5jb20vd2F0Y2g_dj1hYWFhYWFhYWFhYQ.mp4",
+            app_config.video_path
+        )
+    );
+    // "dmlkZW8" is "video" as base64
+    let legacy = format!("{}/video.mp4", app_config.video_path);
+    assert_ne!(download_path("dmlkZW8", &app_config), legacy);
+    std::fs::write(&legacy, "").unwrap();
+    assert_eq!(download_path("dmlkZW8", &app_config), legacy);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use chrono::DateTime;
+use common::items;
+use youtube_subscriptions::feed::{Flag, Item};
+use youtube_subscriptions::query::Query;
+
+fn titles(query: &str, videos: &[Item]) -> Vec<String> {
+    let now = DateTime::parse_from_rfc3339("2026-10-01T00:00:00+00:00").unwrap();
+    let query = Query::parse_at(query, &now).unwrap();
+    videos
+        .iter()
+        .filter(|video| query.matches(video))
+        .map(|video| video.title.clone())
+        .collect()
+}
+
+#[test]
+fn matches_fields_and_operators() {
+    let mut videos = items("podcast.xml", "https://latenightlinux.com/feed");
+    videos.extend(items("atom.xml", "https://bridge.example.org/feed"));
+    videos.extend(items(
+        "youtube.xml",
+        "https://www.youtube.com/feeds/videos.xml",
+    ));
+    let all = titles("", &videos);
+    assert_eq!(all.len(), videos.len());
+
+    assert_eq!(
+        titles("channel:\"late night\"", &videos),
+        vec!["Episode 300"]
+    );
+    assert_eq!(titles("kind:audio title:300", &videos), vec!["Episode 300"]);
+    assert_eq!(titles("duration:>30m", &videos), vec!["Episode 300"]);
+    assert!(titles("duration:<=30m", &videos).is_empty());
+    assert_eq!(titles("after:2d", &videos).len(), 1);
+    assert_eq!(
+        titles("after:2d", &videos),
+        titles("after:2026-09-29", &videos)
+    );
+    assert!(titles("before:2000-01-01", &videos).is_empty());
+
+    videos[0].flag = Flag::Watched;
+    assert_eq!(titles("-flag:read", &videos).len(), all.len() - 1);
+    assert_eq!(titles("is:read", &videos).len(), 1);
+}
+
+#[test]
+fn falls_back_to_fuzzy_matching() {
+    let mut videos = items("podcast.xml", "https://latenightlinux.com/feed");
+    assert_eq!(titles("lnl ep300", &videos), vec!["Episode 300"]);
+    assert!(titles("lnl -ep300", &videos).is_empty());
+    // words which are not fields are matched as they are
+    videos[0].title = "Re:Zero season 3".to_string();
+    assert_eq!(titles("re:zero", &videos), vec!["Re:Zero season 3"]);
+    assert!(titles("-re:zero", &videos).is_empty());
+    assert!(titles("color:red", &videos).is_empty());
+}
+
+#[test]
+fn reports_invalid_queries() {
+    for query in [
+        "duration:<ten",
+        "after:yesterday",
+        "after:é",
+        "before:2dé",
+        "title:\"open",
+    ] {
+        assert!(Query::parse(query).is_err(), "{}", query);
+    }
+}
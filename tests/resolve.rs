@@ -0,0 +1,151 @@
+mod common;
+
+use common::fixture;
+use youtube_subscriptions::config::{edit_channel, remove_outlines, AppConfig};
+use youtube_subscriptions::resolve::{channel_id_in_page, channels_in_page, Channel};
+
+const ID: &str = "UCXuqSBlHAE6Xw-yeJA0Tunw";
+
+#[test]
+fn parses_channels() {
+    let id = Channel::Id(ID.to_string());
+    assert_eq!(Channel::parse(ID), Some(id.clone()));
+    assert_eq!(
+        Channel::parse(&format!("https://www.youtube.com/channel/{}/videos", ID)),
+        Some(id.clone())
+    );
+    assert_eq!(Channel::parse(&id.feed_url()), Some(id.clone()));
+    assert_eq!(
+        Channel::parse("https://framatube.org/feeds/videos.xml?videoChannelId=1"),
+        Some(Channel::Url(
+            "https://framatube.org/feeds/videos.xml?videoChannelId=1".to_string()
+        ))
+    );
+    // handles and channel pages need fetching the page
+    assert_eq!(Channel::parse("@LinusTechTips"), None);
+    assert_eq!(
+        Channel::parse("https://www.youtube.com/@LinusTechTips"),
+        None
+    );
+    assert_eq!(Channel::parse("https://youtube.com/@LinusTechTips"), None);
+    assert_eq!(Channel::parse("https://m.youtube.com/@LinusTechTips"), None);
+    // other hosts ending with youtube.com are feeds like any other
+    assert_eq!(
+        Channel::parse("https://notyoutube.com/@LinusTechTips"),
+        Some(Channel::Url(
+            "https://notyoutube.com/@LinusTechTips".to_string()
+        ))
+    );
+    assert_eq!(Channel::parse("not a channel"), None);
+    assert_eq!(
+        Channel::parse(&format!("www.youtube.com/channel/{}", ID)),
+        Some(id.clone())
+    );
+    // channel ids are only read from YouTube urls
+    let bridge = format!(
+        "https://rss-bridge.org/bridge01/?action=display&bridge=YoutubeBridge&channel_id={}&format=Atom",
+        ID
+    );
+    assert_eq!(Channel::parse(&bridge), Some(Channel::Url(bridge.clone())));
+    assert!(!id.has_feed(&bridge));
+
+    assert!(id.has_feed(&format!(
+        "https://www.youtube.com/feeds/videos.xml?channel_id={}",
+        ID
+    )));
+    assert!(!id.has_feed("https://latenightlinux.com/feed"));
+}
+
+#[test]
+fn adds_and_removes_channels() {
+    let mut app_config = AppConfig::default();
+    let id = Channel::Id(ID.to_string());
+    let url = Channel::Url("https://latenightlinux.com/feed".to_string());
+    assert!(app_config.add_channel(&id));
+    assert!(!app_config.add_channel(&id));
+    assert!(app_config.add_channel(&url));
+    assert_eq!(app_config.channel_ids, vec![ID]);
+    assert_eq!(
+        app_config.channel_urls,
+        vec!["https://latenightlinux.com/feed"]
+    );
+
+    app_config.channel_urls.push(id.feed_url());
+    assert!(app_config.remove_channel(&id));
+    assert!(app_config.channel_ids.is_empty());
+    assert_eq!(
+        app_config.channel_urls,
+        vec!["https://latenightlinux.com/feed"]
+    );
+    assert!(!app_config.remove_channel(&id));
+}
+
+#[test]
+fn edits_only_the_channel_lists_of_the_configuration() {
+    let dir = std::env::temp_dir().join(format!("yts-edit-channel-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.json");
+    let path = path.to_str().unwrap();
+    std::fs::write(path, r#"{"sort": "asc", "unknown": 1}"#).unwrap();
+    let id = Channel::Id(ID.to_string());
+    assert!(edit_channel(path, &id, true).unwrap());
+    assert!(!edit_channel(path, &id, true).unwrap());
+    let config = std::fs::read_to_string(path).unwrap();
+    assert!(config.contains(ID));
+    assert!(config.contains("unknown"));
+    // defaults are not written out
+    assert!(!config.contains("cache_path"));
+    assert!(!config.contains("channel_urls"));
+    assert!(config.find("sort") < config.find("channel_ids"));
+
+    assert!(edit_channel(path, &id, false).unwrap());
+    assert!(!std::fs::read_to_string(path).unwrap().contains(ID));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn removes_channels_from_opml() {
+    let xml = format!(
+        r#"<opml><body><outline text="YouTube Subscriptions">
+<outline text="LTT" xmlUrl="https://www.youtube.com/feeds/videos.xml?channel_id={}"/>
+<outline text="LNL" xmlUrl="https://latenightlinux.com/feed"></outline>
+</outline></body></opml>"#,
+        ID
+    );
+    let removed = remove_outlines(&xml, &Channel::Id(ID.to_string()));
+    assert!(!removed.contains(ID));
+    assert!(removed.contains("latenightlinux"));
+    assert!(roxmltree::Document::parse(&removed).is_ok());
+    let removed = remove_outlines(
+        &removed,
+        &Channel::Url("https://latenightlinux.com/feed".to_string()),
+    );
+    assert!(!removed.contains("latenightlinux"));
+    assert!(removed.contains("YouTube Subscriptions"));
+}
+
+#[test]
+fn finds_channel_ids_in_pages() {
+    // the channel of the page, not the first one linked
+    assert_eq!(
+        channel_id_in_page(&fixture("channel_page.html")),
+        Some(ID.to_string())
+    );
+    assert_eq!(
+        channel_id_in_page(
+            r#"<a href="https://www.youtube.com/channel/UCsXVk37bltHxD1rDPwtNM8Q">"#
+        ),
+        Some("UCsXVk37bltHxD1rDPwtNM8Q".to_string())
+    );
+    assert_eq!(channel_id_in_page(&fixture("atom.xml")), None);
+
+    assert_eq!(
+        channels_in_page(&fixture("channels_export.html")),
+        vec![
+            "@LinusTechTips",
+            "@TechnologyConnections",
+            "UCsXVk37bltHxD1rDPwtNM8Q",
+            "@mkbhd"
+        ]
+    );
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::items;
+use youtube_subscriptions::cache::{merge_state, Items};
+use youtube_subscriptions::feed::{Flag, Item};
+use youtube_subscriptions::query::Query;
+use youtube_subscriptions::rules::{Rule, Rules};
+
+const RULES: &str = r#"[
+    { "name": "gpu", "regex": "(?i)gpu" },
+    { "name": "fans", "field": "Description", "regex": "^Fans", "action": "MarkRead" },
+    { "name": "ltt", "field": "Channel", "regex": "Linus", "action": "Tag" },
+    { "name": "nothing", "field": "Channel", "regex": "^Not Linus$" },
+    { "name": "broken", "field": "Url", "regex": "(" }
+]"#;
+
+fn fetch(rules: &Rules) -> Vec<Item> {
+    let mut videos = items("youtube.xml", "https://www.youtube.com/feeds/videos.xml");
+    for video in videos.iter_mut() {
+        rules.apply(video);
+    }
+    videos
+}
+
+#[test]
+fn applies_rules_to_fetched_items() {
+    let mut videos = items("youtube.xml", "https://www.youtube.com/feeds/videos.xml");
+    videos[0].flag = Flag::Starred;
+    let messages = std::sync::Mutex::new(vec![]);
+    let rules: Vec<Rule> = serde_json::from_str(RULES).unwrap();
+    let rules = Rules::new(&rules, &|s| messages.lock().unwrap().push(s.to_string()));
+    assert_eq!(messages.lock().unwrap().len(), 1);
+    for video in videos.iter_mut() {
+        rules.apply(video);
+        // running them again changes nothing
+        rules.apply(video);
+    }
+
+    let gpu = &videos[0];
+    assert_eq!(gpu.title, "This GPU review is different");
+    assert!(gpu.hidden);
+    assert_eq!(gpu.flag, Flag::Starred);
+    assert_eq!(gpu.rules, vec!["gpu", "ltt"]);
+    // only tag rules tag
+    assert_eq!(gpu.tags, vec!["ltt"]);
+
+    let quiet = &videos[1];
+    assert!(!quiet.hidden);
+    assert_eq!(quiet.flag, Flag::Watched);
+    assert_eq!(quiet.rules, vec!["fans", "ltt"]);
+    assert_eq!(quiet.tags, vec!["ltt"]);
+
+    let query = Query::parse("tag:LTT").unwrap();
+    assert!(videos.iter().all(|video| query.matches(video)));
+}
+
+#[test]
+fn keeps_user_changes_over_rules_on_refresh() {
+    let rules: Vec<Rule> = serde_json::from_str(RULES).unwrap();
+    let mut original = Items {
+        // before the ltt rule was added
+        videos: fetch(&Rules::new(&rules[..2], &|_| ())),
+        ..Default::default()
+    };
+    // :unhide, then tagged back as unread
+    original.videos[0].hidden = false;
+    original.videos[1].flag = Flag::Unread;
+
+    let mut refreshed = Items {
+        videos: fetch(&Rules::new(&rules, &|_| ())),
+        ..Default::default()
+    };
+    merge_state(&mut refreshed, &original, "2026-10-01T00:00:00+02:00");
+    assert!(!refreshed.videos[0].hidden);
+    assert_eq!(refreshed.videos[0].rules, vec!["gpu"]);
+    assert_eq!(refreshed.videos[1].flag, Flag::Unread);
+    assert_eq!(refreshed.videos[1].rules, vec!["fans"]);
+    // rules added since only apply to new items
+    assert!(refreshed.videos.iter().all(|video| video.tags.is_empty()));
+}
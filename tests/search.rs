@@ -0,0 +1,109 @@
+mod common;
+
+use common::items;
+use youtube_subscriptions::cache::Items;
+use youtube_subscriptions::config::AppConfig;
+use youtube_subscriptions::feed::Item;
+use youtube_subscriptions::search::{
+    highlight, item_text, load_index, snippet, terms, update_index, SearchIndex,
+};
+
+fn titles(index: &SearchIndex, query: &str, videos: &[Item]) -> Vec<String> {
+    index
+        .search(query)
+        .iter()
+        .map(|result| {
+            videos
+                .iter()
+                .find(|video| video.id == result.id)
+                .unwrap()
+                .title
+                .clone()
+        })
+        .collect()
+}
+
+#[test]
+fn searches_descriptions_and_contents() {
+    let mut videos = items("podcast.xml", "https://latenightlinux.com/feed");
+    videos.extend(items("atom.xml", "https://bridge.example.org/feed"));
+    videos.extend(items("rdf.xml", "https://lwn.net/headlines/rss"));
+    videos.extend(items(
+        "youtube.xml",
+        "https://www.youtube.com/feeds/videos.xml",
+    ));
+    let index = SearchIndex::build(&videos, None);
+
+    assert_eq!(titles(&index, "twelve", &videos), vec!["Episode 12"]);
+    assert_eq!(
+        titles(&index, "every card", &videos),
+        vec!["This GPU review is different"]
+    );
+    assert_eq!(
+        titles(&index, "Development KERNEL", &videos),
+        vec!["Kernel release status"]
+    );
+    // the last word may be the start of a word
+    assert_eq!(
+        titles(&index, "quiet", &videos),
+        vec!["We built the quietest PC"]
+    );
+    assert_eq!(titles(&index, "episode 300", &videos), vec!["Episode 300"]);
+    assert!(titles(&index, "episode gpu", &videos).is_empty());
+    assert!(index.search("").is_empty());
+
+    // a word in the title ranks higher than in the description
+    let mut other = videos[0].clone();
+    other.id = "other".to_string();
+    other.title = "Another one".to_string();
+    other.description = "not about gpu at all".to_string();
+    other.content = None;
+    videos.push(other);
+    let index = SearchIndex::build(&videos, Some(index));
+    assert_eq!(
+        titles(&index, "gpu", &videos),
+        vec!["This GPU review is different", "Another one"]
+    );
+}
+
+#[test]
+fn highlights_matches() {
+    let videos = items("podcast.xml", "https://latenightlinux.com/feed");
+    let text = item_text(&videos[0]);
+    assert!(text.contains("300th"));
+    assert!(!text.contains("<p>"));
+    let words = terms("300TH");
+    assert_eq!(
+        snippet("a  very\nlong text about the 300th episode", &words, 15),
+        Some("… the 300th epis…".to_string())
+    );
+    assert_eq!(snippet("nothing here", &words, 10), None);
+    assert_eq!(
+        highlight("The 300th episode", &terms("300 epi"), "[", "]"),
+        "The [300th] [episode]"
+    );
+}
+
+#[test]
+fn indexes_edited_items_again() {
+    let dir = std::env::temp_dir().join(format!("yts-search-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let app_config = AppConfig {
+        cache_path: dir.join("cache.json").to_str().unwrap().to_string(),
+        ..Default::default()
+    };
+    let mut videos = Items {
+        videos: items("podcast.xml", "https://latenightlinux.com/feed"),
+        ..Default::default()
+    };
+    update_index(&app_config, &videos).unwrap();
+    videos.videos[0].title = "Renamed episode".to_string();
+    videos.videos[0].channel = "Late Night Linux Extra".to_string();
+    update_index(&app_config, &videos).unwrap();
+    let index = load_index(&app_config).unwrap();
+    assert_eq!(
+        titles(&index, "renamed extra", &videos.videos),
+        vec!["Renamed episode"]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,14 @@
+use youtube_subscriptions::term::fit_width;
+
+#[test]
+fn fits_lines_to_the_screen_width() {
+    assert_eq!(fit_width("abcdef", 4), "abcd");
+    assert_eq!(fit_width("abc", 4), "abc");
+    assert_eq!(fit_width("abc", 0), "");
+    assert_eq!(
+        fit_width("\x1b[34;1mabc\x1b[0mdef", 4),
+        "\x1b[34;1mabc\x1b[0md"
+    );
+    // chinese characters take two columns
+    assert_eq!(fit_width("中文字", 5), "中文");
+}